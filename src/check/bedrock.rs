@@ -1,9 +1,73 @@
+use crate::check::dashboard::{AWS_TAB, Dashboard};
+use crate::check::{Check, CheckResult};
 use crate::models::{BedrockCheckResult, BedrockConfig};
+use crate::validation::{ConfigIssue, validate_aws_credentials};
 use aws_config::BehaviorVersion;
 use aws_sdk_bedrock::Client;
 use aws_sdk_bedrock::config::Region;
+use std::collections::HashMap;
+use std::future::Future;
 use tracing::{debug, error, info, instrument};
 
+/// AWS Bedrock checks
+/// Format: BEDROCK_{identifier}_{param}
+pub struct BedrockCheck;
+
+impl Check for BedrockCheck {
+    type Config = BedrockConfig;
+    type Result = BedrockCheckResult;
+
+    const KIND: &'static str = "bedrock";
+    const LABEL: &'static str = "AWS Bedrock";
+    const PREFIX: &'static str = "BEDROCK_";
+    const PARAMS: &'static [&'static str] = &["region", "access_key_id", "secret_access_key"];
+    const REQUIRED: &'static [&'static [&'static str]] = &[];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: AWS_TAB,
+        renderer: "bedrock",
+        icon: "🤖",
+        colors: ["#f97316", "#ea580c"],
+        success_label: "Accessible",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<BedrockConfig> {
+        // Bedrock only requires region (and optional credentials)
+        Some(BedrockConfig {
+            identifier: identifier.to_string(),
+            region: params
+                .get("region")
+                .cloned()
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            access_key_id: params.get("access_key_id").cloned(),
+            secret_access_key: params.get("secret_access_key").cloned(),
+        })
+    }
+
+    fn run(config: BedrockConfig) -> impl Future<Output = BedrockCheckResult> + Send {
+        check_bedrock(config)
+    }
+
+    fn error_result(config: &BedrockConfig, error: String) -> BedrockCheckResult {
+        BedrockCheckResult {
+            success: false,
+            region: config.region.clone(),
+            model_count: None,
+            models: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        validate_aws_credentials(params).into_iter().collect()
+    }
+}
+
+impl CheckResult for BedrockCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check AWS Bedrock availability and list foundation models
 #[instrument(skip(config), fields(identifier = %config.identifier))]
 pub async fn check_bedrock(config: BedrockConfig) -> BedrockCheckResult {
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// How the dashboard shows the results of a check kind
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Dashboard {
+    /// Tab showing the kind, kinds sharing a tab are shown one after the other
+    pub tab: DashboardTab,
+
+    /// Card renderer of the results in the dashboard script (e.g. "sql"), "generic" shows every
+    /// scalar field of the result
+    pub renderer: &'static str,
+
+    /// Icon of the section of the kind
+    pub icon: &'static str,
+
+    /// Colors of the section of the kind, from light to dark
+    pub colors: [&'static str; 2],
+
+    /// Summary label of successful results (e.g. "Connected")
+    pub success_label: &'static str,
+}
+
+impl Dashboard {
+    /// Generic cards in the "Other Checks" tab, for kinds without their own presentation
+    pub const GENERIC: Dashboard = Dashboard {
+        tab: OTHER_TAB,
+        renderer: "generic",
+        icon: "🧩",
+        colors: ["#64748b", "#475569"],
+        success_label: "Healthy",
+    };
+}
+
+/// Tab of the dashboard grouping check kinds
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DashboardTab {
+    /// Identifier of the tab
+    pub id: &'static str,
+
+    /// Label of the tab button
+    pub label: &'static str,
+
+    /// Header of the tab content
+    pub title: &'static str,
+}
+
+pub const HTTP_TAB: DashboardTab = DashboardTab {
+    id: "http",
+    label: "🌐 HTTP APIs",
+    title: "HTTP API Endpoints",
+};
+
+pub const DATABASES_TAB: DashboardTab = DashboardTab {
+    id: "databases",
+    label: "🗄️ Databases",
+    title: "Database Connections",
+};
+
+pub const AWS_TAB: DashboardTab = DashboardTab {
+    id: "aws",
+    label: "☁️ AWS Services",
+    title: "AWS Cloud Services",
+};
+
+pub const OTHER_TAB: DashboardTab = DashboardTab {
+    id: "other",
+    label: "🧩 Other Checks",
+    title: "Other Checks",
+};
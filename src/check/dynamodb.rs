@@ -1,9 +1,79 @@
+use crate::check::dashboard::{DATABASES_TAB, Dashboard};
+use crate::check::{Check, CheckResult};
 use crate::models::{DynamoDBCheckResult, DynamoDBConfig};
+use crate::validation::{ConfigIssue, validate_aws_credentials};
 use aws_config::BehaviorVersion;
 use aws_sdk_dynamodb::Client;
 use aws_sdk_dynamodb::config::Region;
+use std::collections::HashMap;
+use std::future::Future;
 use tracing::{debug, error, info, instrument};
 
+/// DynamoDB checks
+/// Format: DYNAMODB_{identifier}_{param}
+pub struct DynamoDBCheck;
+
+impl Check for DynamoDBCheck {
+    type Config = DynamoDBConfig;
+    type Result = DynamoDBCheckResult;
+
+    const KIND: &'static str = "dynamodb";
+    const LABEL: &'static str = "DynamoDB Tables";
+    const PREFIX: &'static str = "DYNAMODB_";
+    const PARAMS: &'static [&'static str] =
+        &["table", "region", "access_key_id", "secret_access_key"];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["table"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: DATABASES_TAB,
+        renderer: "dynamodb",
+        icon: "⚡",
+        colors: ["#06b6d4", "#0891b2"],
+        success_label: "Accessible",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<DynamoDBConfig> {
+        // Only parse if table is specified
+        let table = params.get("table")?;
+
+        Some(DynamoDBConfig {
+            identifier: identifier.to_string(),
+            region: params
+                .get("region")
+                .cloned()
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            table: table.clone(),
+            access_key_id: params.get("access_key_id").cloned(),
+            secret_access_key: params.get("secret_access_key").cloned(),
+        })
+    }
+
+    fn run(config: DynamoDBConfig) -> impl Future<Output = DynamoDBCheckResult> + Send {
+        check_dynamodb(config)
+    }
+
+    fn error_result(config: &DynamoDBConfig, error: String) -> DynamoDBCheckResult {
+        DynamoDBCheckResult {
+            success: false,
+            region: config.region.clone(),
+            table: config.table.clone(),
+            status: None,
+            item_count: None,
+            table_size_bytes: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        validate_aws_credentials(params).into_iter().collect()
+    }
+}
+
+impl CheckResult for DynamoDBCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check a DynamoDB table and retrieve information
 #[instrument(skip(config), fields(identifier = %config.identifier, table = %config.table))]
 pub async fn check_dynamodb(config: DynamoDBConfig) -> DynamoDBCheckResult {
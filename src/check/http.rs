@@ -1,52 +1,184 @@
-use crate::models::{HttpCheckResult, HttpConfig};
-use reqwest::Client;
+use crate::check::dashboard::{Dashboard, HTTP_TAB};
+use crate::check::{Check, CheckResult, duration_ms};
+use crate::models::{HttpCheckResult, HttpConfig, HttpTimings};
+use crate::telemetry::inject_trace_context;
+use crate::validation::ConfigIssue;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method, Url};
 use std::collections::HashMap;
-use std::time::Duration;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+use tower::{Layer, Service};
 use tracing::{debug, error, info, instrument};
 
+/// HTTP API checks
+/// Format: HTTP_{identifier}_{param}
+pub struct HttpCheck;
+
+/// Supported values of HTTP_{id}_METHOD
+const METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD"];
+
+impl Check for HttpCheck {
+    type Config = HttpConfig;
+    type Result = HttpCheckResult;
+
+    const KIND: &'static str = "http";
+    const LABEL: &'static str = "HTTP APIs";
+    const PREFIX: &'static str = "HTTP_";
+    const PARAMS: &'static [&'static str] = &["url", "method", "headers"];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["url"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: HTTP_TAB,
+        renderer: "http",
+        icon: "🌐",
+        colors: ["#f59e0b", "#d97706"],
+        success_label: "Healthy",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<HttpConfig> {
+        // Only parse if URL is specified
+        let url = params.get("url")?;
+
+        let method = params
+            .get("method")
+            .cloned()
+            .unwrap_or_else(|| "GET".to_string())
+            .to_uppercase();
+
+        let headers = params
+            .get("headers")
+            .and_then(|h| serde_json::from_str::<HashMap<String, String>>(h).ok())
+            .unwrap_or_default();
+
+        Some(HttpConfig {
+            identifier: identifier.to_string(),
+            url: url.clone(),
+            method,
+            headers,
+        })
+    }
+
+    fn run(config: HttpConfig) -> impl Future<Output = HttpCheckResult> + Send {
+        check_http(config)
+    }
+
+    fn error_result(config: &HttpConfig, error: String) -> HttpCheckResult {
+        HttpCheckResult {
+            success: false,
+            url: config.url.clone(),
+            method: config.method.clone(),
+            status_code: None,
+            response_headers: None,
+            response_body: None,
+            timings: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        if let Some(url) = params.get("url")
+            && let Err(e) = Url::parse(url)
+        {
+            issues.push(ConfigIssue::error(
+                "url",
+                format!("Invalid URL '{}': {}", url, e),
+            ));
+        }
+
+        if let Some(method) = params.get("method")
+            && !METHODS.contains(&method.to_uppercase().as_str())
+        {
+            issues.push(ConfigIssue::error(
+                "method",
+                format!(
+                    "Unsupported method '{}', expected one of: {}",
+                    method,
+                    METHODS.join(", ")
+                ),
+            ));
+        }
+
+        if let Some(headers) = params.get("headers")
+            && let Err(e) = serde_json::from_str::<HashMap<String, String>>(headers)
+        {
+            issues.push(ConfigIssue::error(
+                "headers",
+                format!(
+                    "Invalid JSON object of header names to values, no header is sent: {}",
+                    e
+                ),
+            ));
+        }
+
+        issues
+    }
+}
+
+impl CheckResult for HttpCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check an HTTP API endpoint
 #[instrument(skip(config), fields(identifier = %config.identifier, url = %config.url, method = %config.method))]
 pub async fn check_http(config: HttpConfig) -> HttpCheckResult {
     info!("Checking HTTP API: {}", config.identifier);
 
-    // Create HTTP client with timeout
-    let client = Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .unwrap_or_else(|_| Client::new());
-
-    debug!("Making {} request to {}", config.method, config.url);
+    let failed = |config: &HttpConfig, error: String| {
+        error!("{}", error);
+        HttpCheck::error_result(config, error)
+    };
 
     // Build the request based on method
-    let request_builder = match config.method.as_str() {
-        "GET" => client.get(&config.url),
-        "POST" => client.post(&config.url),
-        "PUT" => client.put(&config.url),
-        "DELETE" => client.delete(&config.url),
-        "PATCH" => client.patch(&config.url),
-        "HEAD" => client.head(&config.url),
-        method => {
-            error!("Unsupported HTTP method: {}", method);
-            return HttpCheckResult {
-                success: false,
-                url: config.url,
-                method: method.to_string(),
-                status_code: None,
-                response_headers: None,
-                response_body: None,
-                error: Some(format!("Unsupported HTTP method: {}", method)),
-            };
+    let method = match Method::from_bytes(config.method.as_bytes()) {
+        Ok(method) if METHODS.contains(&config.method.as_str()) => method,
+        _ => {
+            let error = format!("Unsupported HTTP method: {}", config.method);
+            return failed(&config, error);
         }
     };
 
-    // Add custom headers
-    let mut request_builder = request_builder;
+    // Add custom headers, then the trace context so the target service joins our trace
+    let mut headers = HeaderMap::new();
     for (key, value) in &config.headers {
-        request_builder = request_builder.header(key, value);
+        match (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => return failed(&config, format!("Invalid header '{}'", key)),
+        }
     }
+    inject_trace_context(&mut headers);
 
-    // Send the request
-    let response_result = request_builder.send().await;
+    // Create an HTTP client recording the phases of the request (the check timeout bounds the
+    // whole request)
+    let phases = Arc::new(Mutex::new(RequestPhases::default()));
+    let client = match http_client(&phases) {
+        Ok(client) => client,
+        Err(e) => return failed(&config, format!("Failed to create HTTP client: {}", e)),
+    };
+
+    debug!("Making {} request to {}", config.method, config.url);
+    let response_result = client
+        .request(method, &config.url)
+        .headers(headers)
+        .send()
+        .await;
+    if response_result.is_ok() {
+        lock(&phases).first_byte.get_or_insert_with(Instant::now);
+    }
+    let timings = lock(&phases).timings();
 
     match response_result {
         Ok(response) => {
@@ -75,6 +207,7 @@ pub async fn check_http(config: HttpConfig) -> HttpCheckResult {
                         status_code: Some(status_code),
                         response_headers: Some(response_headers),
                         response_body: Some(body),
+                        timings: Some(timings),
                         error: None,
                     }
                 }
@@ -87,12 +220,14 @@ pub async fn check_http(config: HttpConfig) -> HttpCheckResult {
                         status_code: Some(status_code),
                         response_headers: Some(response_headers),
                         response_body: None,
+                        timings: Some(timings),
                         error: Some(format!("Failed to read response body: {}", e)),
                     }
                 }
             }
         }
         Err(e) => {
+            let e = error_chain(&e);
             error!("HTTP request failed: {}", e);
             HttpCheckResult {
                 success: false,
@@ -101,8 +236,356 @@ pub async fn check_http(config: HttpConfig) -> HttpCheckResult {
                 status_code: None,
                 response_headers: None,
                 response_body: None,
+                timings: Some(timings),
                 error: Some(format!("Request failed: {}", e)),
             }
         }
     }
 }
+
+/// Client recording the phases of its connections in `phases`
+/// The DNS phase is timed by the resolver, and the connection (DNS, TCP connect, proxy tunnel and
+/// TLS handshake) by a layer around the connector of reqwest
+fn http_client(phases: &Arc<Mutex<RequestPhases>>) -> reqwest::Result<Client> {
+    Client::builder()
+        .dns_resolver(Arc::new(TimedResolver {
+            phases: phases.clone(),
+        }))
+        .connector_layer(TimedLayer {
+            phases: phases.clone(),
+        })
+        .build()
+}
+
+/// Message of an error followed by its sources, which reqwest leaves out of its own messages
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        let text = error.to_string();
+        if !message.contains(&text) {
+            message = format!("{}: {}", message, text);
+        }
+        source = error.source();
+    }
+
+    message
+}
+
+/// Phases of the first connection and of the response of a check, which the timings describe
+/// Later connections (e.g. to the target of a redirect) only count in the time to first byte
+#[derive(Debug, Default)]
+struct RequestPhases {
+    /// DNS resolution, absent for IP addresses
+    resolved: Option<Duration>,
+
+    /// DNS resolution and connection, and when the connection was ready
+    established: Option<(Duration, Instant)>,
+
+    /// When the headers of the final response were received
+    first_byte: Option<Instant>,
+}
+
+impl RequestPhases {
+    /// Duration of each phase
+    /// Phases that could not be measured (e.g. after a failure) are left empty
+    fn timings(&self) -> HttpTimings {
+        HttpTimings {
+            dns_ms: self.resolved.map(duration_ms),
+            connect_ms: self.established.map(|(established, _)| {
+                duration_ms(established.saturating_sub(self.resolved.unwrap_or_default()))
+            }),
+            first_byte_ms: self
+                .established
+                .zip(self.first_byte)
+                .map(|((_, ready), first_byte)| duration_ms(first_byte.duration_since(ready))),
+        }
+    }
+}
+
+fn lock(phases: &Mutex<RequestPhases>) -> MutexGuard<'_, RequestPhases> {
+    phases.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// System DNS resolver recording how long the first resolution takes
+struct TimedResolver {
+    phases: Arc<Mutex<RequestPhases>>,
+}
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let phases = self.phases.clone();
+        let host = name.as_str().to_string();
+
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = lookup_host((host, 0)).await?;
+            lock(&phases).resolved.get_or_insert(started.elapsed());
+            Ok(Box::new(addrs) as Addrs)
+        })
+    }
+}
+
+/// Connector layer recording how long the first connection takes to be ready
+#[derive(Clone)]
+struct TimedLayer {
+    phases: Arc<Mutex<RequestPhases>>,
+}
+
+impl<S> Layer<S> for TimedLayer {
+    type Service = Timed<S>;
+
+    fn layer(&self, inner: S) -> Timed<S> {
+        Timed {
+            inner,
+            phases: self.phases.clone(),
+        }
+    }
+}
+
+/// Connector service of [`TimedLayer`]
+#[derive(Clone)]
+struct Timed<S> {
+    inner: S,
+    phases: Arc<Mutex<RequestPhases>>,
+}
+
+impl<S, T> Service<T> for Timed<S>
+where
+    S: Service<T>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let started = Instant::now();
+        let connection = self.inner.call(target);
+        let phases = self.phases.clone();
+
+        Box::pin(async move {
+            let result = connection.await;
+            if result.is_ok() {
+                lock(&phases)
+                    .established
+                    .get_or_insert((started.elapsed(), Instant::now()));
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::global;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    use tracing::Instrument;
+    use tracing::instrument::WithSubscriber;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Serve the given responses to successive connections after `delay`, closing each
+    /// connection, and return the requests received
+    async fn serve(
+        responses: Vec<String>,
+        delay: Duration,
+    ) -> (SocketAddr, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                requests.push(String::from_utf8_lossy(&request).to_lowercase());
+
+                tokio::time::sleep(delay).await;
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        (addr, server)
+    }
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        )
+    }
+
+    fn config(url: String, headers: &[(&str, &str)]) -> HttpConfig {
+        HttpConfig {
+            identifier: "LOCAL".to_string(),
+            url,
+            method: "GET".to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_context_is_sent() {
+        let (addr, server) = serve(vec![response("200 OK", "", "ok")], Duration::ZERO).await;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = TracerProvider::builder().build().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+        let url = format!("http://localhost:{}/health", addr.port());
+        let (result, trace_id) = async {
+            let span = tracing::info_span!("check");
+            let trace_id = span.context().span().span_context().trace_id();
+            (
+                check_http(config(url, &[])).instrument(span).await,
+                trace_id,
+            )
+        }
+        .with_subscriber(subscriber)
+        .await;
+        assert!(result.success, "{:?}", result.error);
+
+        // The request carries the trace of the span running the check
+        let requests = server.await.unwrap();
+        let traceparent = requests[0]
+            .lines()
+            .find_map(|line| line.strip_prefix("traceparent: "))
+            .unwrap();
+        assert!(
+            traceparent.starts_with(&format!("00-{}-", trace_id)),
+            "{}",
+            traceparent
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timings_are_measured_on_the_request_connection() {
+        let (addr, server) = serve(
+            vec![response("200 OK", "", "ok")],
+            Duration::from_millis(50),
+        )
+        .await;
+
+        let result = check_http(config(
+            format!("http://localhost:{}/health", addr.port()),
+            &[],
+        ))
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.response_body.as_deref(), Some("ok"));
+
+        // A single connection is opened, the one of the request
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("get /health http/1.1"));
+
+        let timings = result.timings.unwrap();
+        assert!(timings.dns_ms.is_some());
+        assert!(timings.connect_ms.is_some());
+
+        // The time to first byte excludes the connection, and includes the server delay
+        let first_byte_ms = timings.first_byte_ms.unwrap();
+        assert!(first_byte_ms >= 50.0, "{}", first_byte_ms);
+    }
+
+    #[tokio::test]
+    async fn test_failed_connection_keeps_measured_phases() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let result = check_http(config(format!("http://127.0.0.1:{}/", port), &[])).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().starts_with("Request failed"));
+
+        // IP addresses are not resolved, and the connection was refused
+        let timings = result.timings.unwrap();
+        assert_eq!(timings.dns_ms, None);
+        assert_eq!(timings.connect_ms, None);
+        assert_eq!(timings.first_byte_ms, None);
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_followed_without_leaking_credentials() {
+        let (target, target_server) =
+            serve(vec![response("200 OK", "", "moved")], Duration::ZERO).await;
+        let (addr, server) = serve(
+            vec![
+                response("302 Found", "location: /next\r\n", ""),
+                response(
+                    "307 Temporary Redirect",
+                    &format!("location: http://localhost:{}/final\r\n", target.port()),
+                    "",
+                ),
+            ],
+            Duration::ZERO,
+        )
+        .await;
+
+        let result = check_http(config(
+            format!("http://127.0.0.1:{}/start", addr.port()),
+            &[("Authorization", "Bearer secret"), ("Accept", "text/plain")],
+        ))
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(result.response_body.as_deref(), Some("moved"));
+
+        // Credentials are kept on the same host, and removed for another one
+        let requests = server.await.unwrap();
+        assert!(requests[1].starts_with("get /next"));
+        assert!(requests[1].contains("authorization: bearer secret"));
+        let requests = target_server.await.unwrap();
+        assert!(requests[0].starts_with("get /final"));
+        assert!(!requests[0].contains("authorization"));
+        assert!(requests[0].contains("accept: text/plain"));
+    }
+
+    #[test]
+    fn test_phase_durations() {
+        let ready = Instant::now();
+        let phases = RequestPhases {
+            resolved: Some(Duration::from_millis(5)),
+            established: Some((Duration::from_millis(45), ready)),
+            first_byte: Some(ready + Duration::from_millis(20)),
+        };
+
+        let timings = phases.timings();
+        assert_eq!(timings.dns_ms, Some(5.0));
+        assert_eq!(timings.connect_ms, Some(40.0));
+        assert_eq!(timings.first_byte_ms, Some(20.0));
+
+        // Without DNS resolution, the connection takes the whole time until it is ready
+        let phases = RequestPhases {
+            resolved: None,
+            ..phases
+        };
+        assert_eq!(phases.timings().connect_ms, Some(45.0));
+    }
+}
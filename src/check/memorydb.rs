@@ -1,9 +1,79 @@
+use crate::check::dashboard::{AWS_TAB, Dashboard};
+use crate::check::{Check, CheckResult};
 use crate::models::{MemoryDBCheckResult, MemoryDBConfig};
+use crate::validation::{ConfigIssue, validate_aws_credentials};
 use aws_config::BehaviorVersion;
 use aws_sdk_memorydb::Client;
 use aws_sdk_memorydb::config::Region;
+use std::collections::HashMap;
+use std::future::Future;
 use tracing::{debug, error, info, instrument};
 
+/// MemoryDB checks
+/// Format: MEMORYDB_{identifier}_{param}
+pub struct MemoryDBCheck;
+
+impl Check for MemoryDBCheck {
+    type Config = MemoryDBConfig;
+    type Result = MemoryDBCheckResult;
+
+    const KIND: &'static str = "memorydb";
+    const LABEL: &'static str = "MemoryDB Clusters";
+    const PREFIX: &'static str = "MEMORYDB_";
+    const PARAMS: &'static [&'static str] =
+        &["cluster", "region", "access_key_id", "secret_access_key"];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["cluster"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: AWS_TAB,
+        renderer: "memorydb",
+        icon: "💜",
+        colors: ["#8b5cf6", "#7c3aed"],
+        success_label: "Accessible",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<MemoryDBConfig> {
+        // Only parse if cluster is specified
+        let cluster = params.get("cluster")?;
+
+        Some(MemoryDBConfig {
+            identifier: identifier.to_string(),
+            region: params
+                .get("region")
+                .cloned()
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            cluster: cluster.clone(),
+            access_key_id: params.get("access_key_id").cloned(),
+            secret_access_key: params.get("secret_access_key").cloned(),
+        })
+    }
+
+    fn run(config: MemoryDBConfig) -> impl Future<Output = MemoryDBCheckResult> + Send {
+        check_memorydb(config)
+    }
+
+    fn error_result(config: &MemoryDBConfig, error: String) -> MemoryDBCheckResult {
+        MemoryDBCheckResult {
+            success: false,
+            region: config.region.clone(),
+            cluster: config.cluster.clone(),
+            endpoint: None,
+            status: None,
+            node_count: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        validate_aws_credentials(params).into_iter().collect()
+    }
+}
+
+impl CheckResult for MemoryDBCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check a MemoryDB cluster and retrieve information
 #[instrument(skip(config), fields(identifier = %config.identifier, cluster = %config.cluster))]
 pub async fn check_memorydb(config: MemoryDBConfig) -> MemoryDBCheckResult {
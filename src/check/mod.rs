@@ -1,4 +1,6 @@
 pub mod bedrock;
+pub mod connection;
+pub mod dashboard;
 pub mod dynamodb;
 pub mod http;
 pub mod memorydb;
@@ -7,11 +9,971 @@ pub mod s3;
 pub mod secrets_manager;
 pub mod sql;
 
-pub use bedrock::check_bedrock;
-pub use dynamodb::check_dynamodb;
-pub use http::check_http;
-pub use memorydb::check_memorydb;
-pub use nosql::check_nosql;
-pub use s3::check_s3;
-pub use secrets_manager::check_secrets_manager;
-pub use sql::check_sql;
+use crate::config_file::{ConfigFile, load_config_file, load_env_dir};
+use crate::env_parser::{group_params_by_identifier, parse_bool, parse_duration};
+use crate::metrics::record_check_metrics;
+use crate::validation::{ConfigIssue, validate_bool};
+use bedrock::BedrockCheck;
+use chrono::{DateTime, Utc};
+use dashboard::Dashboard;
+use dynamodb::DynamoDBCheck;
+use futures::future::BoxFuture;
+use http::HttpCheck;
+use memorydb::MemoryDBCheck;
+use nosql::NoSqlCheck;
+use s3::S3Check;
+use secrets_manager::SecretsManagerCheck;
+use serde::Serialize;
+use serde_json::Value;
+use sql::SqlCheck;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{Instant, timeout_at};
+use tracing::{error, warn};
+
+/// Timeout of checks that configure neither TIMEOUT nor CHECK_TIMEOUT
+const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// All registered check kinds, in dashboard order.
+/// Adding a new check type only requires a new module implementing [`Check`]
+/// and an entry in this list.
+static REGISTRY: &[&dyn CheckKind] = &[
+    &HttpCheck,
+    &SqlCheck,
+    &NoSqlCheck,
+    &DynamoDBCheck,
+    &S3Check,
+    &MemoryDBCheck,
+    &SecretsManagerCheck,
+    &BedrockCheck,
+];
+
+/// Parameters understood by every check kind
+const COMMON_PARAMS: &[&str] = &["interval", "timeout", "critical"];
+
+/// Parameters of the checks of one kind, keyed by identifier, then parameter name
+pub type CheckParams = HashMap<String, HashMap<String, String>>;
+
+/// Common interface of every check result type
+pub trait CheckResult: Serialize + Send + 'static {
+    /// Whether the check succeeded
+    fn success(&self) -> bool;
+}
+
+/// A check type that is configured from `{PREFIX}{identifier}_{param}` environment variables
+pub trait Check: Send + Sync + 'static {
+    /// Parsed configuration of a single check, serialized with secrets masked
+    type Config: Clone + Serialize + Send + Sync + 'static;
+
+    /// Result returned by running the check
+    type Result: CheckResult;
+
+    /// Kind name, used as the key in the `/_/info` response (e.g. "sql")
+    const KIND: &'static str;
+
+    /// Human readable name shown in the dashboard
+    const LABEL: &'static str;
+
+    /// Environment variable prefix (e.g. "SQL_")
+    const PREFIX: &'static str;
+
+    /// Parameters understood by the check, besides INTERVAL, TIMEOUT and CRITICAL
+    const PARAMS: &'static [&'static str];
+
+    /// Presentation of the results in the dashboard
+    const DASHBOARD: Dashboard = Dashboard::GENERIC;
+
+    /// Parameters without which the check is not configured
+    /// Each entry lists alternatives, one of which must be set (e.g. DRIVER or URL)
+    const REQUIRED: &'static [&'static [&'static str]];
+
+    /// Build a configuration from the parameters grouped under one identifier.
+    /// Returns `None` if required parameters are missing.
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<Self::Config>;
+
+    /// Run the check against the given configuration
+    fn run(config: Self::Config) -> impl Future<Output = Self::Result> + Send;
+
+    /// Build a failed result for the given configuration (e.g. when the check times out)
+    fn error_result(config: &Self::Config, error: String) -> Self::Result;
+
+    /// Parameters set indirectly through other parameters (e.g. the host of a connection URL),
+    /// which are not listed as defaults
+    fn implied_params(_params: &HashMap<String, String>) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Report parameter values that `parse_config` ignores or replaces with a default
+    fn validate(_params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        Vec::new()
+    }
+}
+
+/// Type-erased view of a [`Check`], used by the registry
+pub trait CheckKind: Send + Sync {
+    /// Kind name (e.g. "sql")
+    fn kind(&self) -> &'static str;
+
+    /// Human readable name shown in the dashboard
+    fn label(&self) -> &'static str;
+
+    /// Environment variable prefix (e.g. "SQL_")
+    fn prefix(&self) -> &'static str;
+
+    /// Presentation of the results in the dashboard
+    fn dashboard(&self) -> Dashboard;
+
+    /// Parse every check of this kind from its parameters grouped by identifier
+    fn parse_checks(&self, params: CheckParams, defaults: &CheckDefaults) -> Vec<CheckInstance>;
+
+    /// Report unknown parameters, missing required parameters and invalid values
+    fn validate(&self, params: &CheckParams) -> Vec<ConfigIssue>;
+}
+
+impl<C: Check> CheckKind for C {
+    fn kind(&self) -> &'static str {
+        C::KIND
+    }
+
+    fn label(&self) -> &'static str {
+        C::LABEL
+    }
+
+    fn prefix(&self) -> &'static str {
+        C::PREFIX
+    }
+
+    fn dashboard(&self) -> Dashboard {
+        C::DASHBOARD
+    }
+
+    fn parse_checks(&self, params: CheckParams, defaults: &CheckDefaults) -> Vec<CheckInstance> {
+        params
+            .into_iter()
+            .filter_map(|(identifier, params)| {
+                let config = C::parse_config(&identifier, &params)?;
+                let interval = params
+                    .get("interval")
+                    .and_then(|v| parse_interval(v))
+                    .or(defaults.interval);
+                let timeout = params
+                    .get("timeout")
+                    .and_then(|v| parse_duration(v))
+                    .unwrap_or(defaults.timeout);
+                let critical = params
+                    .get("critical")
+                    .and_then(|v| parse_bool(v))
+                    .unwrap_or(true);
+
+                let described = serde_json::to_value(&config).unwrap_or_default();
+                let implied = C::implied_params(&params);
+                let defaults = defaulted_fields(C::PARAMS, &params, &implied, &described, interval);
+
+                Some(CheckInstance {
+                    kind: C::KIND,
+                    identifier,
+                    interval,
+                    timeout,
+                    critical,
+                    params,
+                    config: described,
+                    defaults,
+                    runner: Arc::new(move |limit| {
+                        let config = config.clone();
+                        Box::pin(async move {
+                            let started = Instant::now();
+
+                            match timeout_at(limit, C::run(config.clone())).await {
+                                Ok(result) => CheckOutput::from_result(result, started.elapsed()),
+                                Err(_) => {
+                                    let elapsed = started.elapsed();
+                                    let error = format!(
+                                        "Check timed out after {:.1}s",
+                                        elapsed.as_secs_f64()
+                                    );
+                                    warn!(kind = C::KIND, "{}", error);
+
+                                    let mut output = CheckOutput::from_result(
+                                        C::error_result(&config, error),
+                                        elapsed,
+                                    );
+                                    output.timed_out = true;
+                                    output
+                                }
+                            }
+                        })
+                    }),
+                })
+            })
+            .collect()
+    }
+
+    fn validate(&self, params: &CheckParams) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        for (identifier, params) in params {
+            let known = |param: &str| C::PARAMS.contains(&param) || COMMON_PARAMS.contains(&param);
+            let mut check_issues = Vec::new();
+
+            let mut unknown: Vec<_> = params.keys().filter(|param| !known(param)).collect();
+            unknown.sort();
+            for param in unknown {
+                check_issues.push(ConfigIssue::warning(param, "Unknown parameter, ignored"));
+            }
+
+            // Groups without any known parameter are most likely unrelated variables
+            if params.keys().any(|param| known(param)) {
+                for alternatives in C::REQUIRED {
+                    if alternatives.iter().any(|param| params.contains_key(*param)) {
+                        continue;
+                    }
+
+                    let message = match alternatives {
+                        [_] => "Missing required parameter, the check is ignored".to_string(),
+                        _ => format!(
+                            "Missing required parameter (one of {}), the check is ignored",
+                            alternatives.join(", ")
+                        ),
+                    };
+                    check_issues.push(ConfigIssue::error(alternatives[0], message));
+                }
+            }
+
+            for param in ["interval", "timeout"] {
+                if let Some(value) = params.get(param)
+                    && parse_duration(value).is_none()
+                {
+                    check_issues.push(ConfigIssue::error(
+                        param,
+                        format!("Invalid duration '{}', using the default", value),
+                    ));
+                }
+            }
+
+            if let Some(value) = params.get("interval")
+                && parse_duration(value) == Some(Duration::ZERO)
+            {
+                check_issues.push(ConfigIssue::error(
+                    "interval",
+                    "The interval must be greater than zero, using the default",
+                ));
+            }
+
+            check_issues.extend(validate_bool(params, "critical", "true"));
+
+            check_issues.extend(C::validate(params));
+            issues.extend(
+                check_issues
+                    .into_iter()
+                    .map(|issue| issue.for_check(C::KIND, identifier)),
+            );
+        }
+
+        issues
+    }
+}
+
+/// A configured check, ready to be run
+#[derive(Clone)]
+pub struct CheckInstance {
+    /// Kind name of the check
+    pub kind: &'static str,
+
+    /// Identifier taken from the environment variable names or the config file
+    pub identifier: String,
+
+    /// How often the background scheduler runs the check ({PREFIX}{id}_INTERVAL)
+    pub interval: Option<Duration>,
+
+    /// Maximum duration of a single run ({PREFIX}{id}_TIMEOUT)
+    pub timeout: Duration,
+
+    /// Whether a failure makes the service not ready ({PREFIX}{id}_CRITICAL, default true)
+    pub critical: bool,
+
+    /// Parameters the check was parsed from, used to detect changes on reload
+    pub params: HashMap<String, String>,
+
+    /// Parsed configuration, with secrets masked
+    pub config: Value,
+
+    /// Configuration fields and settings that were not set and use their default
+    pub defaults: Vec<String>,
+
+    runner: Arc<dyn Fn(Instant) -> BoxFuture<'static, CheckOutput> + Send + Sync>,
+}
+
+impl CheckInstance {
+    /// Run the check, giving up once its timeout elapses
+    pub async fn run(&self) -> CheckOutput {
+        self.run_until(None).await
+    }
+
+    /// Run the check, giving up once its timeout elapses or the deadline is reached
+    pub async fn run_until(&self, deadline: Option<Instant>) -> CheckOutput {
+        let limit = Instant::now() + self.timeout;
+        let limit = deadline.map_or(limit, |deadline| limit.min(deadline));
+
+        let output = (self.runner)(limit).await;
+        record_check_metrics(self.kind, &self.identifier, &output);
+
+        output
+    }
+}
+
+/// List the parameters of a check that were not set but have a value in its configuration,
+/// and the settings taken from CHECK_INTERVAL, CHECK_TIMEOUT or the built-in defaults
+fn defaulted_fields(
+    known: &[&str],
+    params: &HashMap<String, String>,
+    implied: &[&str],
+    config: &Value,
+    interval: Option<Duration>,
+) -> Vec<String> {
+    let mut defaults: Vec<String> = known
+        .iter()
+        .filter(|param| !params.contains_key(**param) && !implied.contains(param))
+        .filter(|param| config.get(**param).is_some_and(|value| !value.is_null()))
+        .map(|param| param.to_string())
+        .collect();
+
+    for setting in COMMON_PARAMS {
+        let has_value = *setting != "interval" || interval.is_some();
+        if has_value && !params.contains_key(*setting) {
+            defaults.push(setting.to_string());
+        }
+    }
+
+    defaults.sort();
+    defaults
+}
+
+/// Parse a scheduler interval, which must be greater than zero
+fn parse_interval(value: &str) -> Option<Duration> {
+    parse_duration(value).filter(|interval| !interval.is_zero())
+}
+
+/// Settings applied to every check that does not override them
+#[derive(Debug, Clone)]
+pub struct CheckDefaults {
+    /// Default scheduler interval (CHECK_INTERVAL)
+    pub interval: Option<Duration>,
+
+    /// Default timeout (CHECK_TIMEOUT, 10s if unset)
+    pub timeout: Duration,
+}
+
+impl CheckDefaults {
+    /// Read the defaults from the variables, falling back to the defaults of the config file
+    fn from_vars(vars: &[(String, String)], file: Option<&ConfigFile>) -> Self {
+        let get = |name: &str, param: &str| {
+            vars.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value)
+                .or_else(|| file.and_then(|file| file.defaults.get(param)))
+                .and_then(|value| parse_duration(value))
+        };
+
+        Self {
+            interval: get("CHECK_INTERVAL", "interval").filter(|interval| !interval.is_zero()),
+            timeout: get("CHECK_TIMEOUT", "timeout").unwrap_or(DEFAULT_CHECK_TIMEOUT),
+        }
+    }
+}
+
+/// Serialized result of a check, independent of its kind
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutput {
+    /// Whether the check succeeded
+    #[serde(skip)]
+    pub success: bool,
+
+    /// Kind-specific result
+    #[serde(flatten)]
+    pub result: Value,
+
+    /// When the check finished
+    pub checked_at: DateTime<Utc>,
+
+    /// Total duration of the check, in milliseconds
+    pub duration_ms: f64,
+
+    /// Seconds elapsed since the check finished (only for cached results)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age: Option<u64>,
+
+    /// Whether the check was aborted because it exceeded its timeout
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+}
+
+impl CheckOutput {
+    fn from_result<R: CheckResult>(result: R, elapsed: Duration) -> Self {
+        let success = result.success();
+        let checked_at = Utc::now();
+        let duration_ms = duration_ms(elapsed);
+
+        match serde_json::to_value(&result) {
+            Ok(result) => Self {
+                success,
+                result,
+                checked_at,
+                duration_ms,
+                age: None,
+                timed_out: false,
+            },
+            Err(e) => {
+                error!("Failed to serialize check result: {}", e);
+                Self {
+                    success: false,
+                    result: serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to serialize check result: {}", e),
+                    }),
+                    checked_at,
+                    duration_ms,
+                    age: None,
+                    timed_out: false,
+                }
+            }
+        }
+    }
+}
+
+/// Convert a duration to fractional milliseconds, as reported in check results
+pub fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Check results grouped by kind, then keyed by identifier
+pub type CheckResults = BTreeMap<String, HashMap<String, CheckOutput>>;
+
+/// Return all registered check kinds
+pub fn registry() -> &'static [&'static dyn CheckKind] {
+    REGISTRY
+}
+
+/// Find a registered check kind by name
+pub fn find_kind(kind: &str) -> Option<&'static dyn CheckKind> {
+    REGISTRY.iter().copied().find(|k| k.kind() == kind)
+}
+
+/// Checks parsed from the configuration
+pub struct LoadedChecks {
+    /// Every check that could be parsed
+    pub checks: Vec<CheckInstance>,
+
+    /// Configuration sources that could not be loaded (CONFIG_DIR, CONFIG_FILE)
+    pub errors: Vec<String>,
+
+    /// Problems found in the configuration, including the sources that could not be loaded
+    pub issues: Vec<ConfigIssue>,
+}
+
+/// Parse all configured checks from the process environment, CONFIG_DIR and CONFIG_FILE
+pub fn load_checks() -> LoadedChecks {
+    load_checks_from(env::vars().collect())
+}
+
+/// Parse all configured checks from the given variables, the files in the directory they
+/// reference and the config file they reference
+/// Files in CONFIG_DIR override the variables, variables override the parameters of a check
+/// with the same kind and identifier in CONFIG_FILE
+pub fn load_checks_from(vars: Vec<(String, String)>) -> LoadedChecks {
+    let mut errors = Vec::new();
+    let mut issues = Vec::new();
+
+    let vars = match lookup(&vars, "CONFIG_DIR") {
+        Some(dir) => match load_env_dir(dir) {
+            Ok(dir_vars) => override_vars(vars, dir_vars),
+            Err(e) => {
+                issues.push(ConfigIssue::error(
+                    "CONFIG_DIR",
+                    format!("{:#}, ignored", e),
+                ));
+                errors.push(format!("{:#}", e));
+                vars
+            }
+        },
+        None => vars,
+    };
+
+    let mut file =
+        lookup(&vars, "CONFIG_FILE").and_then(|path| match load_config_file(path, &vars) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                issues.push(ConfigIssue::error(
+                    "CONFIG_FILE",
+                    format!("{:#}, ignored", e),
+                ));
+                errors.push(format!("{:#}", e));
+                None
+            }
+        });
+
+    if let Some(file) = &file {
+        for kind in file.checks.keys() {
+            if find_kind(kind).is_none() {
+                issues.push(ConfigIssue {
+                    kind: Some(kind.clone()),
+                    ..ConfigIssue::warning(
+                        "CONFIG_FILE",
+                        "Unknown check kind, its checks are ignored",
+                    )
+                });
+            }
+        }
+    }
+
+    for (name, param) in [("CHECK_INTERVAL", "interval"), ("CHECK_TIMEOUT", "timeout")] {
+        let (setting, value) = match lookup(&vars, name) {
+            Some(value) => (name.to_string(), value),
+            None => match file.as_ref().and_then(|file| file.defaults.get(param)) {
+                Some(value) => (format!("defaults.{}", param), value.as_str()),
+                None => continue,
+            },
+        };
+
+        match parse_duration(value) {
+            None => issues.push(ConfigIssue::error(
+                setting,
+                format!("Invalid duration '{}', ignored", value),
+            )),
+            Some(Duration::ZERO) if param == "interval" => issues.push(ConfigIssue::error(
+                setting,
+                "The interval must be greater than zero, ignored",
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let defaults = CheckDefaults::from_vars(&vars, file.as_ref());
+
+    let checks = REGISTRY
+        .iter()
+        .flat_map(|kind| {
+            let mut params = file
+                .as_mut()
+                .and_then(|file| file.checks.remove(kind.kind()))
+                .unwrap_or_default();
+
+            for (identifier, env_params) in group_params_by_identifier(&vars, kind.prefix()) {
+                params.entry(identifier).or_default().extend(env_params);
+            }
+
+            issues.extend(kind.validate(&params));
+            kind.parse_checks(params, &defaults)
+        })
+        .collect();
+
+    LoadedChecks {
+        checks,
+        errors,
+        issues,
+    }
+}
+
+/// Find the non-empty value of a variable
+fn lookup<'a>(vars: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    vars.iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .filter(|value| !value.is_empty())
+}
+
+/// Replace the variables that are also defined in the overrides
+fn override_vars(
+    vars: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let mut merged = vars;
+
+    // Later files also override earlier ones, keep the last value of each variable
+    for (key, value) in overrides {
+        merged.retain(|(name, _)| *name != key);
+        merged.push((key, value));
+    }
+
+    merged
+}
+
+/// Run the given checks concurrently and group their results by kind
+/// Checks still running at the deadline are reported as timed out
+pub async fn run_checks(checks: Vec<CheckInstance>, deadline: Option<Instant>) -> CheckResults {
+    let tasks = checks.into_iter().map(|check| async move {
+        let output = check.run_until(deadline).await;
+        (check.kind, check.identifier, output)
+    });
+
+    let mut results = CheckResults::new();
+    for (kind, identifier, output) in futures::future::join_all(tasks).await {
+        results
+            .entry(kind.to_string())
+            .or_default()
+            .insert(identifier, output);
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Severity;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_registry_kinds_are_unique() {
+        let mut kinds: Vec<_> = registry().iter().map(|kind| kind.kind()).collect();
+        let total = kinds.len();
+        kinds.sort();
+        kinds.dedup();
+
+        assert_eq!(kinds.len(), total);
+    }
+
+    /// Check that never completes, used to test timeouts
+    struct StuckCheck;
+
+    #[derive(Serialize)]
+    struct StuckResult {
+        success: bool,
+        error: Option<String>,
+    }
+
+    impl CheckResult for StuckResult {
+        fn success(&self) -> bool {
+            self.success
+        }
+    }
+
+    impl Check for StuckCheck {
+        type Config = ();
+        type Result = StuckResult;
+
+        const KIND: &'static str = "stuck";
+        const LABEL: &'static str = "Stuck";
+        const PREFIX: &'static str = "STUCK_";
+        const PARAMS: &'static [&'static str] = &[];
+        const REQUIRED: &'static [&'static [&'static str]] = &[];
+
+        fn parse_config(_identifier: &str, _params: &HashMap<String, String>) -> Option<()> {
+            Some(())
+        }
+
+        async fn run(_config: ()) -> StuckResult {
+            futures::future::pending().await
+        }
+
+        fn error_result(_config: &(), error: String) -> StuckResult {
+            StuckResult {
+                success: false,
+                error: Some(error),
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_settings_and_defaults() {
+        let vars = vars(&[
+            ("CHECK_TIMEOUT", "3s"),
+            ("CHECK_INTERVAL", "1m"),
+            ("STUCK_A_TIMEOUT", "500ms"),
+            ("STUCK_A_CRITICAL", "false"),
+            ("STUCK_B_INTERVAL", "5s"),
+            ("STUCK_C_INTERVAL", "0s"),
+        ]);
+        let defaults = CheckDefaults::from_vars(&vars, None);
+        let mut checks =
+            StuckCheck.parse_checks(group_params_by_identifier(&vars, "STUCK_"), &defaults);
+        checks.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+
+        assert_eq!(checks[0].timeout, Duration::from_millis(500));
+        assert_eq!(checks[0].interval, Some(Duration::from_secs(60)));
+        assert!(!checks[0].critical);
+        assert_eq!(checks[1].timeout, Duration::from_secs(3));
+        assert_eq!(checks[1].interval, Some(Duration::from_secs(5)));
+        assert!(checks[1].critical);
+
+        // A zero interval is ignored
+        assert_eq!(checks[2].interval, Some(Duration::from_secs(60)));
+        let defaults = CheckDefaults::from_vars(&self::vars(&[("CHECK_INTERVAL", "0")]), None);
+        assert_eq!(defaults.interval, None);
+    }
+
+    #[tokio::test]
+    async fn test_check_times_out() {
+        let vars = vars(&[("STUCK_A_TIMEOUT", "50ms")]);
+        let defaults = CheckDefaults::from_vars(&vars, None);
+        let check = StuckCheck
+            .parse_checks(group_params_by_identifier(&vars, "STUCK_"), &defaults)
+            .remove(0);
+
+        let output = check.run().await;
+
+        assert!(!output.success);
+        assert!(output.timed_out);
+        assert!(
+            output.result["error"]
+                .as_str()
+                .unwrap()
+                .contains("timed out")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deadline_shortens_timeout() {
+        let vars = vars(&[("STUCK_A_TIMEOUT", "1h")]);
+        let defaults = CheckDefaults::from_vars(&vars, None);
+        let check = StuckCheck
+            .parse_checks(group_params_by_identifier(&vars, "STUCK_"), &defaults)
+            .remove(0);
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let results = run_checks(vec![check], Some(deadline)).await;
+
+        assert!(results["stuck"]["A"].timed_out);
+    }
+
+    #[test]
+    fn test_load_checks_from_vars() {
+        let loaded = load_checks_from(vars(&[
+            ("SQL_MAIN_DRIVER", "postgres"),
+            ("SQL_NODRIVER_HOST", "localhost"),
+            ("NOSQL_CACHE_DRIVER", "redis"),
+            ("HTTP_API_URL", "http://localhost"),
+            ("BEDROCK_AI_REGION", "eu-west-1"),
+            ("UNRELATED", "value"),
+        ]));
+
+        assert!(loaded.errors.is_empty());
+        let mut found: Vec<_> = loaded
+            .checks
+            .iter()
+            .map(|check| (check.kind, check.identifier.as_str()))
+            .collect();
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                ("bedrock", "AI"),
+                ("http", "API"),
+                ("nosql", "CACHE"),
+                ("sql", "MAIN"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_checks_merges_config_file() {
+        let path = env::temp_dir().join(format!("pmp-checks-{}.yaml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+defaults:
+  timeout: 3s
+checks:
+  sql:
+    main_db:
+      driver: postgres
+      host: ${DB_HOST:-localhost}
+      critical: false
+    primary:
+      driver: postgres
+      critical: false
+"#,
+        )
+        .unwrap();
+
+        let loaded = load_checks_from(vars(&[
+            ("CONFIG_FILE", path.to_str().unwrap()),
+            ("SQL_primary_CRITICAL", "true"),
+            ("SQL_OTHER_DRIVER", "mysql"),
+        ]));
+        std::fs::remove_file(&path).unwrap();
+
+        let mut found: Vec<_> = loaded
+            .checks
+            .iter()
+            .map(|check| (check.identifier.as_str(), check.critical, check.timeout))
+            .collect();
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                ("OTHER", true, Duration::from_secs(3)),
+                ("main_db", false, Duration::from_secs(3)),
+                ("primary", true, Duration::from_secs(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_checks_from_config_dir() {
+        let dir = env::temp_dir().join(format!("pmp-checks-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("checks.env"), "SQL_MAIN_CRITICAL=false\n").unwrap();
+
+        let loaded = load_checks_from(vars(&[
+            ("CONFIG_DIR", dir.to_str().unwrap()),
+            ("SQL_MAIN_DRIVER", "postgres"),
+            ("SQL_MAIN_CRITICAL", "true"),
+        ]));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(loaded.errors.is_empty());
+        assert_eq!(loaded.checks.len(), 1);
+        assert!(!loaded.checks[0].critical);
+
+        let missing = load_checks_from(vars(&[("CONFIG_DIR", "/nonexistent/pmp-checks")]));
+        assert_eq!(missing.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_load_checks_reports_issues() {
+        let loaded = load_checks_from(vars(&[
+            ("CHECK_TIMEOUT", "soon"),
+            ("CHECK_INTERVAL", "0"),
+            ("SQL_MAIN_HOST", "db"),
+            ("SQL_MAIN_PASWORD", "typo"),
+            ("SQL_REPLICA_DRIVER", "postgres"),
+            ("SQL_REPLICA_PORT", "five"),
+            ("SQL_RDS_URL", "postgres://app:secret@db:port/orders"),
+            ("SQL_LOCAL_URL", "sqlite://"),
+            ("SQL_AZURE_URL", "sqlserver://db/orders"),
+            ("SQL_AZURE_TLS_CERT", "/certs/client.crt"),
+            ("SQL_AZURE_TLS_KEY", "/certs/client.key"),
+            ("SQL_AZURE_QUERY", "DELETE FROM sessions; COMMIT"),
+            ("HTTP_API_URL", "http://localhost"),
+            ("HTTP_API_HEADERS", "{\"Authorization\": \"Bearer x\""),
+            ("HTTP_API_CRITICAL", "maybe"),
+            ("HTTP_API_INTERVAL", "0s"),
+            ("HTTP_PROXY_SETTINGS", "unrelated"),
+        ]));
+
+        let mut found: Vec<_> = loaded
+            .issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.severity,
+                    issue.identifier.as_deref().unwrap_or_default(),
+                    issue.param.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Warning, "AZURE", "tls_cert"),
+                (Severity::Warning, "MAIN", "pasword"),
+                (Severity::Warning, "PROXY", "settings"),
+                (Severity::Error, "", "CHECK_INTERVAL"),
+                (Severity::Error, "", "CHECK_TIMEOUT"),
+                (Severity::Error, "API", "critical"),
+                (Severity::Error, "API", "headers"),
+                (Severity::Error, "API", "interval"),
+                (Severity::Error, "AZURE", "query"),
+                (Severity::Error, "LOCAL", "url"),
+                (Severity::Error, "MAIN", "driver"),
+                (Severity::Error, "RDS", "url"),
+                (Severity::Error, "REPLICA", "port"),
+            ]
+        );
+        assert!(loaded.errors.is_empty());
+
+        // Messages never contain connection URLs, which may contain passwords
+        let messages: Vec<_> = loaded.issues.iter().map(|i| i.to_string()).collect();
+        assert!(messages.iter().all(|message| !message.contains("secret")));
+        assert!(messages.contains(
+            &"sql/MAIN driver: Missing required parameter (one of driver, url), the check is ignored"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn test_effective_config_masks_secrets_and_lists_defaults() {
+        let loaded = load_checks_from(vars(&[
+            ("CHECK_INTERVAL", "1m"),
+            ("SQL_MAIN_DRIVER", "postgres"),
+            ("SQL_MAIN_PORT", "5433"),
+            ("SQL_MAIN_PASSWORD", "secret"),
+            ("SQL_MAIN_CRITICAL", "false"),
+            ("HTTP_API_URL", "https://api.example.com"),
+            (
+                "HTTP_API_HEADERS",
+                r#"{"Authorization": "Bearer abc", "Accept": "text/plain"}"#,
+            ),
+        ]));
+        let find = |kind: &str| {
+            loaded
+                .checks
+                .iter()
+                .find(|check| check.kind == kind)
+                .unwrap()
+        };
+
+        let sql = find("sql");
+        assert_eq!(sql.config["password"], "(value is set)");
+        assert_eq!(sql.config["port"], 5433);
+        assert_eq!(
+            sql.defaults,
+            vec![
+                "database",
+                "host",
+                "interval",
+                "max_rows",
+                "options",
+                "probe_table",
+                "schemas",
+                "server_status",
+                "timeout",
+                "user",
+                "write_probe"
+            ]
+        );
+
+        // Parts of a connection URL are not defaults
+        let loaded = load_checks_from(vars(&[(
+            "SQL_MAIN_URL",
+            "mysql://app:secret@db/orders?ssl-mode=required",
+        )]));
+        let sql = &loaded.checks[0];
+        assert_eq!(sql.config["driver"], "mysql");
+        assert_eq!(sql.config["password"], "(value is set)");
+        assert_eq!(
+            sql.defaults,
+            vec![
+                "critical",
+                "max_rows",
+                "port",
+                "probe_table",
+                "schemas",
+                "server_status",
+                "timeout",
+                "write_probe"
+            ]
+        );
+
+        let http = find("http");
+        assert_eq!(http.config["headers"]["Authorization"], "(value is set)");
+        assert_eq!(http.config["headers"]["Accept"], "text/plain");
+        assert_eq!(
+            http.defaults,
+            vec!["critical", "interval", "method", "timeout"]
+        );
+    }
+}
@@ -1,9 +1,126 @@
-use crate::models::{NoSqlCheckResult, NoSqlConfig};
+use crate::check::connection::{
+    ConnectionUrl, connection_url, parse_options, url_params, validate_options, validate_url,
+};
+use crate::check::dashboard::{DATABASES_TAB, Dashboard};
+use crate::check::{Check, CheckResult, duration_ms};
+use crate::models::{NoSqlCheckResult, NoSqlConfig, NoSqlTimings};
+use crate::validation::{ConfigIssue, validate_one_of, validate_parse};
 use redis::aio::ConnectionManager;
 use redis::{Client, RedisError};
 use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
 use tracing::{debug, error, info, instrument};
 
+/// NoSQL database checks
+/// Format: NOSQL_{identifier}_{param}
+pub struct NoSqlCheck;
+
+/// Supported values of NOSQL_{id}_DRIVER
+const DRIVERS: &[&str] = &["redis"];
+
+impl Check for NoSqlCheck {
+    type Config = NoSqlConfig;
+    type Result = NoSqlCheckResult;
+
+    const KIND: &'static str = "nosql";
+    const LABEL: &'static str = "NoSQL Databases";
+    const PREFIX: &'static str = "NOSQL_";
+    const PARAMS: &'static [&'static str] = &[
+        "driver", "url", "host", "port", "user", "password", "database", "options",
+    ];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["driver", "url"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: DATABASES_TAB,
+        renderer: "nosql",
+        icon: "🔥",
+        colors: ["#10b981", "#059669"],
+        success_label: "Connected",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<NoSqlConfig> {
+        // Only parse if driver or a valid URL is specified, separate parameters override the URL
+        let url = match params.get("url") {
+            Some(url) => ConnectionUrl::parse(url).ok()?,
+            None => ConnectionUrl::default(),
+        };
+        let driver = params
+            .get("driver")
+            .cloned()
+            .or_else(|| params.contains_key("url").then(|| url.scheme.clone()))?;
+
+        let mut options = url.options;
+        options.extend(
+            params
+                .get("options")
+                .and_then(|o| parse_options(o).ok())
+                .unwrap_or_default(),
+        );
+
+        Some(NoSqlConfig {
+            identifier: identifier.to_string(),
+            driver,
+            host: params
+                .get("host")
+                .cloned()
+                .or(url.host)
+                .unwrap_or_else(|| "localhost".to_string()),
+            port: params
+                .get("port")
+                .and_then(|p| p.parse().ok())
+                .or(url.port)
+                .unwrap_or(6379),
+            user: params.get("user").cloned().or(url.user),
+            password: params.get("password").cloned().or(url.password),
+            database: params
+                .get("database")
+                .or(url.database.as_ref())
+                .and_then(|d| d.parse().ok())
+                .unwrap_or_default(),
+            options,
+        })
+    }
+
+    fn run(config: NoSqlConfig) -> impl Future<Output = NoSqlCheckResult> + Send {
+        check_nosql(config)
+    }
+
+    fn error_result(config: &NoSqlConfig, error: String) -> NoSqlCheckResult {
+        NoSqlCheckResult {
+            success: false,
+            driver: config.driver.clone(),
+            host: config.host.clone(),
+            port: config.port,
+            info: None,
+            timings: None,
+            error: Some(error),
+        }
+    }
+
+    fn implied_params(params: &HashMap<String, String>) -> Vec<&'static str> {
+        url_params(params, ConnectionUrl::parse)
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        [
+            validate_one_of(params, "driver", DRIVERS),
+            validate_url(params, DRIVERS, ConnectionUrl::parse),
+            validate_parse::<u16>(params, "port", "a port number", "6379"),
+            validate_parse::<i64>(params, "database", "a database number", "0"),
+            validate_options(params),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+impl CheckResult for NoSqlCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check a NoSQL database connection and retrieve information
 #[instrument(skip(config), fields(identifier = %config.identifier, driver = %config.driver))]
 pub async fn check_nosql(config: NoSqlConfig) -> NoSqlCheckResult {
@@ -19,6 +136,7 @@ pub async fn check_nosql(config: NoSqlConfig) -> NoSqlCheckResult {
                 host: config.host,
                 port: config.port,
                 info: None,
+                timings: None,
                 error: Some(format!("Unsupported NoSQL driver: {}", driver)),
             }
         }
@@ -29,11 +147,15 @@ pub async fn check_nosql(config: NoSqlConfig) -> NoSqlCheckResult {
 #[instrument(skip(config))]
 async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
     // Build Redis connection string
-    let connection_string = if let Some(password) = &config.password {
-        format!("redis://:{}@{}:{}/", password, config.host, config.port)
-    } else {
-        format!("redis://{}:{}/", config.host, config.port)
-    };
+    let connection_string = connection_url(
+        "redis",
+        config.user.as_deref().unwrap_or_default(),
+        config.password.as_deref(),
+        &config.host,
+        config.port,
+        &config.database.to_string(),
+        &config.options,
+    );
 
     debug!("Attempting to connect to Redis");
 
@@ -43,15 +165,22 @@ async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
     match client_result {
         Ok(client) => {
             // Try to get a connection manager
+            let connect_started = Instant::now();
             let manager_result = ConnectionManager::new(client).await;
+            let connect_ms = duration_ms(connect_started.elapsed());
 
             match manager_result {
                 Ok(mut manager) => {
                     debug!("Successfully connected to Redis");
 
                     // Try to ping the server
+                    let ping_started = Instant::now();
                     let ping_result: Result<String, RedisError> =
                         redis::cmd("PING").query_async(&mut manager).await;
+                    let timings = Some(NoSqlTimings {
+                        connect_ms,
+                        ping_ms: Some(duration_ms(ping_started.elapsed())),
+                    });
 
                     match ping_result {
                         Ok(pong) => {
@@ -77,6 +206,7 @@ async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
                                 host: config.host,
                                 port: config.port,
                                 info: Some(info_map),
+                                timings,
                                 error: None,
                             }
                         }
@@ -88,6 +218,7 @@ async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
                                 host: config.host,
                                 port: config.port,
                                 info: None,
+                                timings,
                                 error: Some(format!("PING failed: {}", e)),
                             }
                         }
@@ -101,6 +232,10 @@ async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
                         host: config.host,
                         port: config.port,
                         info: None,
+                        timings: Some(NoSqlTimings {
+                            connect_ms,
+                            ping_ms: None,
+                        }),
                         error: Some(format!("Connection manager creation failed: {}", e)),
                     }
                 }
@@ -114,6 +249,7 @@ async fn check_redis(config: NoSqlConfig) -> NoSqlCheckResult {
                 host: config.host,
                 port: config.port,
                 info: None,
+                timings: None,
                 error: Some(format!("Client creation failed: {}", e)),
             }
         }
@@ -1,9 +1,78 @@
+use crate::check::dashboard::{AWS_TAB, Dashboard};
+use crate::check::{Check, CheckResult};
 use crate::models::{S3CheckResult, S3Config};
+use crate::validation::{ConfigIssue, validate_aws_credentials};
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::Client;
 use aws_sdk_s3::config::Region;
+use std::collections::HashMap;
+use std::future::Future;
 use tracing::{debug, error, info, instrument};
 
+/// S3 bucket checks
+/// Format: S3_{identifier}_{param}
+pub struct S3Check;
+
+impl Check for S3Check {
+    type Config = S3Config;
+    type Result = S3CheckResult;
+
+    const KIND: &'static str = "s3";
+    const LABEL: &'static str = "S3 Buckets";
+    const PREFIX: &'static str = "S3_";
+    const PARAMS: &'static [&'static str] =
+        &["bucket", "region", "access_key_id", "secret_access_key"];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["bucket"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: AWS_TAB,
+        renderer: "s3",
+        icon: "🪣",
+        colors: ["#ec4899", "#db2777"],
+        success_label: "Accessible",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<S3Config> {
+        // Only parse if bucket is specified
+        let bucket = params.get("bucket")?;
+
+        Some(S3Config {
+            identifier: identifier.to_string(),
+            region: params
+                .get("region")
+                .cloned()
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            bucket: bucket.clone(),
+            access_key_id: params.get("access_key_id").cloned(),
+            secret_access_key: params.get("secret_access_key").cloned(),
+        })
+    }
+
+    fn run(config: S3Config) -> impl Future<Output = S3CheckResult> + Send {
+        check_s3(config)
+    }
+
+    fn error_result(config: &S3Config, error: String) -> S3CheckResult {
+        S3CheckResult {
+            success: false,
+            region: config.region.clone(),
+            bucket: config.bucket.clone(),
+            exists: None,
+            object_count: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        validate_aws_credentials(params).into_iter().collect()
+    }
+}
+
+impl CheckResult for S3CheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check an S3 bucket and retrieve information
 #[instrument(skip(config), fields(identifier = %config.identifier, bucket = %config.bucket))]
 pub async fn check_s3(config: S3Config) -> S3CheckResult {
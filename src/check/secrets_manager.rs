@@ -1,9 +1,85 @@
+use crate::check::dashboard::{AWS_TAB, Dashboard};
+use crate::check::{Check, CheckResult};
 use crate::models::{SecretsManagerCheckResult, SecretsManagerConfig};
+use crate::validation::{ConfigIssue, validate_aws_credentials};
 use aws_config::BehaviorVersion;
 use aws_sdk_secretsmanager::Client;
 use aws_sdk_secretsmanager::config::Region;
+use std::collections::HashMap;
+use std::future::Future;
 use tracing::{debug, error, info, instrument};
 
+/// AWS Secrets Manager checks
+/// Format: SECRETS_{identifier}_{param}
+pub struct SecretsManagerCheck;
+
+impl Check for SecretsManagerCheck {
+    type Config = SecretsManagerConfig;
+    type Result = SecretsManagerCheckResult;
+
+    const KIND: &'static str = "secrets_manager";
+    const LABEL: &'static str = "Secrets Manager";
+    const PREFIX: &'static str = "SECRETS_";
+    const PARAMS: &'static [&'static str] = &[
+        "secret_name",
+        "region",
+        "access_key_id",
+        "secret_access_key",
+    ];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["secret_name"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: AWS_TAB,
+        renderer: "secrets_manager",
+        icon: "🔐",
+        colors: ["#ef4444", "#dc2626"],
+        success_label: "Accessible",
+    };
+
+    fn parse_config(
+        identifier: &str,
+        params: &HashMap<String, String>,
+    ) -> Option<SecretsManagerConfig> {
+        // Only parse if secret_name is specified
+        let secret_name = params.get("secret_name")?;
+
+        Some(SecretsManagerConfig {
+            identifier: identifier.to_string(),
+            region: params
+                .get("region")
+                .cloned()
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            secret_name: secret_name.clone(),
+            access_key_id: params.get("access_key_id").cloned(),
+            secret_access_key: params.get("secret_access_key").cloned(),
+        })
+    }
+
+    fn run(config: SecretsManagerConfig) -> impl Future<Output = SecretsManagerCheckResult> + Send {
+        check_secrets_manager(config)
+    }
+
+    fn error_result(config: &SecretsManagerConfig, error: String) -> SecretsManagerCheckResult {
+        SecretsManagerCheckResult {
+            success: false,
+            region: config.region.clone(),
+            secret_name: config.secret_name.clone(),
+            exists: None,
+            version_id: None,
+            error: Some(error),
+        }
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        validate_aws_credentials(params).into_iter().collect()
+    }
+}
+
+impl CheckResult for SecretsManagerCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
 /// Check AWS Secrets Manager secret and retrieve information
 #[instrument(skip(config), fields(identifier = %config.identifier, secret_name = %config.secret_name))]
 pub async fn check_secrets_manager(config: SecretsManagerConfig) -> SecretsManagerCheckResult {
@@ -0,0 +1,137 @@
+use super::mssql::MSSQL;
+use super::mysql::MYSQL;
+use super::postgres::POSTGRES;
+use super::schema::TableColumn;
+use super::sqlite::SQLITE;
+use crate::models::{
+    SqlCheckResult, SqlConfig, SqlQueryResult, SqlServerStatus, SqlTlsStatus, SqlWriteProbeResult,
+};
+use futures::future::BoxFuture;
+use std::future::Future;
+
+/// Reject a validation query with the reason
+pub type CheckQuery = fn(&str) -> Result<(), String>;
+
+/// Connection defaults and implementation of a SQL driver
+/// Adding a driver only requires a module defining its descriptor and [`SqlSession`], and an
+/// entry in [`DRIVERS`]
+pub struct SqlDriver {
+    /// Value of SQL_{id}_DRIVER (e.g. "postgres")
+    pub name: &'static str,
+
+    /// Other accepted values of SQL_{id}_DRIVER (e.g. "postgresql")
+    pub aliases: &'static [&'static str],
+
+    /// Scheme of connection URLs (e.g. "postgres" in `postgres://host/db`)
+    pub scheme: &'static str,
+
+    /// Port used when SQL_{id}_PORT is unset
+    pub default_port: u16,
+
+    /// User used when SQL_{id}_USER is unset
+    pub default_user: &'static str,
+
+    /// Database used when SQL_{id}_DATABASE is unset
+    pub default_database: &'static str,
+
+    /// Whether the database is a file, whose path is everything after the scheme of connection
+    /// URLs (e.g. `sqlite:///data/app.db`) instead of a host and a database name
+    pub file_url: bool,
+
+    /// Whether SQL_{id}_TLS_CERT and SQL_{id}_TLS_KEY are supported
+    pub client_certificate: bool,
+
+    /// Reject validation queries that the driver cannot run read-only, `None` when a read-only
+    /// transaction protects the database
+    pub check_query: Option<CheckQuery>,
+
+    /// Connect to a database of this driver and retrieve information, usually
+    /// [`super::check_database`] with the [`SqlSession`] of the driver
+    pub check: fn(SqlConfig) -> BoxFuture<'static, SqlCheckResult>,
+}
+
+/// Connection of a driver to the database of a check, running the driver-specific SQL of each
+/// step of the check
+/// The steps are run in order, and their results assembled, by [`super::check_database`]
+pub trait SqlSession: Sized + Send {
+    /// Name of the database in logs (e.g. "PostgreSQL")
+    const LABEL: &'static str;
+
+    /// Connect to the database of the check, applying its TLS settings
+    fn connect(config: &SqlConfig) -> impl Future<Output = Result<Self, String>> + Send;
+
+    /// Schemas whose tables are listed when SQL_{id}_SCHEMAS is unset (e.g. "public")
+    fn default_schemas(config: &SqlConfig) -> Vec<String>;
+
+    /// Version of the server
+    fn version(&mut self) -> impl Future<Output = Option<String>> + Send;
+
+    /// Encryption of the session, `None` for databases that are not reached over the network
+    fn tls_status(&mut self) -> impl Future<Output = Option<SqlTlsStatus>> + Send {
+        async { None }
+    }
+
+    /// State of the server, `None` when the driver does not report it
+    fn server_status(&mut self) -> impl Future<Output = Option<SqlServerStatus>> + Send {
+        async { None }
+    }
+
+    /// Schema and name of the tables and views of the given schemas
+    fn list_tables(
+        &mut self,
+        schemas: &[String],
+    ) -> impl Future<Output = Result<Vec<(String, String)>, String>> + Send;
+
+    /// Columns of the tables and views of the given schemas
+    fn list_columns(
+        &mut self,
+        schemas: &[String],
+    ) -> impl Future<Output = Result<Vec<TableColumn>, String>> + Send;
+
+    /// Run the validation query without writing, and compare its result with the expectations
+    fn run_query(
+        &mut self,
+        sql: &str,
+        config: &SqlConfig,
+    ) -> impl Future<Output = SqlQueryResult> + Send;
+
+    /// Write, read back and delete a probe row in the probe table of the check
+    fn run_write_probe(
+        &mut self,
+        config: &SqlConfig,
+    ) -> impl Future<Output = SqlWriteProbeResult> + Send;
+}
+
+/// All supported SQL drivers
+static DRIVERS: &[&SqlDriver] = &[&POSTGRES, &MYSQL, &SQLITE, &MSSQL];
+
+/// Find a driver by name or alias
+pub fn find_driver(name: &str) -> Option<&'static SqlDriver> {
+    DRIVERS
+        .iter()
+        .copied()
+        .find(|driver| driver.name == name || driver.aliases.contains(&name))
+}
+
+/// Every accepted value of SQL_{id}_DRIVER
+pub fn driver_names() -> Vec<&'static str> {
+    DRIVERS
+        .iter()
+        .flat_map(|driver| std::iter::once(driver.name).chain(driver.aliases.iter().copied()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_driver_names_are_unique() {
+        let mut names = driver_names();
+        let total = names.len();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), total);
+    }
+}
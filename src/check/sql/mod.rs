@@ -0,0 +1,521 @@
+pub mod driver;
+pub mod mssql;
+pub mod mysql;
+pub mod postgres;
+pub mod probe;
+pub mod query;
+pub mod schema;
+pub mod sqlite;
+pub mod statement;
+
+use crate::check::connection::{
+    self, ConnectionUrl, parse_options, url_params, validate_options, validate_url,
+};
+use crate::check::dashboard::{DATABASES_TAB, Dashboard};
+use crate::check::{Check, CheckResult, duration_ms};
+use crate::env_parser::parse_bool;
+use crate::models::{RowCountExpectation, SqlCheckResult, SqlConfig, SqlTimings, SqlTlsMode};
+use crate::validation::{ConfigIssue, validate_bool, validate_one_of, validate_parse};
+use driver::{SqlDriver, SqlSession, driver_names, find_driver};
+use probe::{DEFAULT_PROBE_TABLE, is_valid_table_name};
+use query::{DEFAULT_MAX_ROWS, MAX_ROWS_LIMIT, table_name};
+use schema::{check_schema, load_manifest};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+use tracing::{debug, error, info, instrument};
+
+/// SQL database checks
+/// Format: SQL_{identifier}_{param}
+pub struct SqlCheck;
+
+impl Check for SqlCheck {
+    type Config = SqlConfig;
+    type Result = SqlCheckResult;
+
+    const KIND: &'static str = "sql";
+    const LABEL: &'static str = "SQL Databases";
+    const PREFIX: &'static str = "SQL_";
+    const PARAMS: &'static [&'static str] = &[
+        "driver",
+        "url",
+        "host",
+        "port",
+        "user",
+        "password",
+        "database",
+        "options",
+        "tls_mode",
+        "tls_ca",
+        "tls_cert",
+        "tls_key",
+        "server_status",
+        "schemas",
+        "expect_tables",
+        "schema_manifest",
+        "query",
+        "expect_rows",
+        "expect_equals",
+        "expect_contains",
+        "max_rows",
+        "write_probe",
+        "probe_table",
+    ];
+    const REQUIRED: &'static [&'static [&'static str]] = &[&["driver", "url"]];
+    const DASHBOARD: Dashboard = Dashboard {
+        tab: DATABASES_TAB,
+        renderer: "sql",
+        icon: "💾",
+        colors: ["#3b82f6", "#2563eb"],
+        success_label: "Connected",
+    };
+
+    fn parse_config(identifier: &str, params: &HashMap<String, String>) -> Option<SqlConfig> {
+        // Only parse if driver or a valid URL is specified, separate parameters override the URL
+        let url = match params.get("url") {
+            Some(url) => parse_url(params, url).ok()?,
+            None => ConnectionUrl::default(),
+        };
+        let driver = params
+            .get("driver")
+            .cloned()
+            .or_else(|| params.contains_key("url").then(|| url.scheme.clone()))?;
+
+        // Defaults depend on the driver, unsupported drivers are reported when the check runs
+        let descriptor = find_driver(&driver);
+
+        let mut options = url.options;
+        options.extend(
+            params
+                .get("options")
+                .and_then(|o| parse_options(o).ok())
+                .unwrap_or_default(),
+        );
+
+        Some(SqlConfig {
+            identifier: identifier.to_string(),
+            driver: descriptor.map_or(driver, |d| d.name.to_string()),
+            host: params
+                .get("host")
+                .cloned()
+                .or(url.host)
+                .unwrap_or_else(|| "localhost".to_string()),
+            port: params
+                .get("port")
+                .and_then(|p| p.parse().ok())
+                .or(url.port)
+                .or(descriptor.map(|d| d.default_port))
+                .unwrap_or_default(),
+            user: params
+                .get("user")
+                .cloned()
+                .or(url.user)
+                .unwrap_or_else(|| descriptor.map_or("", |d| d.default_user).to_string()),
+            password: params
+                .get("password")
+                .cloned()
+                .or(url.password)
+                .unwrap_or_default(),
+            database: params
+                .get("database")
+                .cloned()
+                .or(url.database)
+                .unwrap_or_else(|| descriptor.map_or("", |d| d.default_database).to_string()),
+            options,
+            tls_mode: params.get("tls_mode").and_then(|m| SqlTlsMode::parse(m)),
+            tls_ca: params.get("tls_ca").cloned(),
+            tls_cert: params.get("tls_cert").cloned(),
+            tls_key: params.get("tls_key").cloned(),
+            server_status: params
+                .get("server_status")
+                .and_then(|s| parse_bool(s))
+                .unwrap_or(true),
+            schemas: params
+                .get("schemas")
+                .map(|s| comma_list(s))
+                .unwrap_or_default(),
+            expect_tables: params
+                .get("expect_tables")
+                .map(|t| comma_list(t))
+                .unwrap_or_default(),
+            schema_manifest: params.get("schema_manifest").cloned(),
+            query: params.get("query").cloned(),
+            expect_rows: params
+                .get("expect_rows")
+                .and_then(|e| RowCountExpectation::parse(e)),
+            expect_equals: params.get("expect_equals").cloned(),
+            expect_contains: params.get("expect_contains").cloned(),
+            max_rows: params
+                .get("max_rows")
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(DEFAULT_MAX_ROWS)
+                .min(MAX_ROWS_LIMIT),
+            write_probe: params
+                .get("write_probe")
+                .and_then(|w| parse_bool(w))
+                .unwrap_or(false),
+            probe_table: params
+                .get("probe_table")
+                .filter(|t| is_valid_table_name(t))
+                .cloned()
+                .unwrap_or_else(|| DEFAULT_PROBE_TABLE.to_string()),
+        })
+    }
+
+    fn run(config: SqlConfig) -> impl Future<Output = SqlCheckResult> + Send {
+        check_sql(config)
+    }
+
+    fn error_result(config: &SqlConfig, error: String) -> SqlCheckResult {
+        SqlCheckResult {
+            error: Some(error),
+            ..empty_result(config)
+        }
+    }
+
+    fn implied_params(params: &HashMap<String, String>) -> Vec<&'static str> {
+        url_params(params, |url| parse_url(params, url))
+    }
+
+    fn validate(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+        [
+            validate_one_of(params, "driver", &driver_names()),
+            validate_url(params, &driver_names(), |url| parse_url(params, url)),
+            validate_parse::<u16>(params, "port", "a port number", "the driver default"),
+            validate_options(params),
+            validate_tls(params),
+            validate_bool(params, "server_status", "true"),
+            validate_manifest(params),
+            validate_bool(params, "write_probe", "false"),
+            validate_probe_table(params),
+            validate_parse::<usize>(params, "max_rows", "a number of rows", "10"),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(validate_query(params))
+        .collect()
+    }
+}
+
+/// Report an unsupported TLS mode, or a client certificate without its key or the opposite
+fn validate_tls(params: &HashMap<String, String>) -> Option<ConfigIssue> {
+    if let Some(mode) = params.get("tls_mode")
+        && SqlTlsMode::parse(mode).is_none()
+    {
+        return Some(ConfigIssue::error(
+            "tls_mode",
+            format!(
+                "Unsupported value '{}', expected one of: {}, using the driver default",
+                mode,
+                SqlTlsMode::NAMES.join(", ")
+            ),
+        ));
+    }
+
+    match (
+        params.contains_key("tls_cert"),
+        params.contains_key("tls_key"),
+    ) {
+        (true, false) => Some(ConfigIssue::error(
+            "tls_key",
+            "TLS_CERT is set without TLS_KEY, the client certificate is not sent",
+        )),
+        (false, true) => Some(ConfigIssue::error(
+            "tls_cert",
+            "TLS_KEY is set without TLS_CERT, the client certificate is not sent",
+        )),
+        (true, true) => params_driver(params)
+            .filter(|driver| !driver.client_certificate)
+            .map(|driver| {
+                ConfigIssue::warning(
+                    "tls_cert",
+                    format!(
+                        "Not supported by the {} driver, the client certificate is not sent",
+                        driver.name
+                    ),
+                )
+            }),
+        _ => None,
+    }
+}
+
+/// Driver of the check, from the DRIVER parameter or the scheme of the URL
+fn params_driver(params: &HashMap<String, String>) -> Option<&'static SqlDriver> {
+    match params.get("driver") {
+        Some(driver) => find_driver(driver),
+        None => find_driver(url_scheme(params.get("url")?)),
+    }
+}
+
+/// Scheme of a connection URL (e.g. `sqlite` in `sqlite:app.db`)
+fn url_scheme(url: &str) -> &str {
+    url.split_once(':').map_or("", |(scheme, _)| scheme)
+}
+
+/// Parse the URL parameter, as a file path for file drivers (e.g. `sqlite:///data/app.db`)
+fn parse_url(params: &HashMap<String, String>, url: &str) -> Result<ConnectionUrl, String> {
+    match params_driver(params) {
+        Some(driver) if driver.file_url => ConnectionUrl::parse_file(url),
+        _ => ConnectionUrl::parse(url),
+    }
+}
+
+/// Report a schema manifest that cannot be loaded, which fails the check
+fn validate_manifest(params: &HashMap<String, String>) -> Option<ConfigIssue> {
+    let path = params.get("schema_manifest")?;
+
+    load_manifest(path).err().map(|e| {
+        ConfigIssue::error(
+            "schema_manifest",
+            format!("Invalid schema manifest, the check fails: {}", e),
+        )
+    })
+}
+
+/// Report a probe table that is not a plain name, and a probe table without write probe
+fn validate_probe_table(params: &HashMap<String, String>) -> Option<ConfigIssue> {
+    let table = params.get("probe_table")?;
+
+    if !is_valid_table_name(table) {
+        return Some(ConfigIssue::error(
+            "probe_table",
+            format!(
+                "Invalid table name '{}', expected letters, digits and underscores optionally preceded by a schema, using {}",
+                table, DEFAULT_PROBE_TABLE
+            ),
+        ));
+    }
+
+    let enabled = params.get("write_probe").and_then(|w| parse_bool(w));
+    (enabled != Some(true))
+        .then(|| ConfigIssue::warning("probe_table", "Ignored without WRITE_PROBE"))
+}
+
+/// Report an invalid row count, and expectations without a query to check them against
+fn validate_query(params: &HashMap<String, String>) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+
+    if let Some(expected) = params.get("expect_rows")
+        && RowCountExpectation::parse(expected).is_none()
+    {
+        issues.push(ConfigIssue::error(
+            "expect_rows",
+            format!(
+                "Invalid row count '{}', expected a number optionally preceded by =, !=, <, <=, > or >=, the row count is not checked",
+                expected
+            ),
+        ));
+    }
+
+    if let Some(sql) = params.get("query")
+        && let Some(driver) = params_driver(params)
+        && let Some(check_query) = driver.check_query
+        && let Err(e) = check_query(sql)
+    {
+        issues.push(ConfigIssue::error(
+            "query",
+            format!(
+                "Rejected by the {} driver, the check fails: {}",
+                driver.name, e
+            ),
+        ));
+    }
+
+    if !params.contains_key("query") {
+        for param in [
+            "expect_rows",
+            "expect_equals",
+            "expect_contains",
+            "max_rows",
+        ] {
+            if params.contains_key(param) {
+                issues.push(ConfigIssue::warning(param, "Ignored without QUERY"));
+            }
+        }
+    }
+
+    issues
+}
+
+impl CheckResult for SqlCheckResult {
+    fn success(&self) -> bool {
+        self.success
+    }
+}
+
+/// Split a comma-separated list, ignoring blank items
+fn comma_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Failed result of the given configuration, without any information, for the drivers to fill in
+pub fn empty_result(config: &SqlConfig) -> SqlCheckResult {
+    SqlCheckResult {
+        success: false,
+        driver: config.driver.clone(),
+        host: config.host.clone(),
+        port: config.port,
+        database: config.database.clone(),
+        version: None,
+        server: None,
+        tables: None,
+        tls: None,
+        schema: None,
+        query: None,
+        write_probe: None,
+        timings: None,
+        error: None,
+    }
+}
+
+/// Connection URL of the given driver, with every part percent-encoded
+pub fn connection_url(driver: &SqlDriver, config: &SqlConfig) -> String {
+    connection::connection_url(
+        driver.scheme,
+        &config.user,
+        Some(&config.password),
+        &config.host,
+        config.port,
+        &config.database,
+        &config.options,
+    )
+}
+
+/// Connect to the database of the check with the session of a driver, then retrieve its
+/// version, state and tables, compare them with the expected schema, and run the validation
+/// query and the write probe
+#[instrument(skip_all, fields(driver = S::LABEL))]
+pub async fn check_database<S: SqlSession>(config: SqlConfig) -> SqlCheckResult {
+    debug!("Attempting to connect to {} database", S::LABEL);
+
+    // The check timeout bounds the connection attempt
+    let connect_started = Instant::now();
+    let session = S::connect(&config).await;
+    let connect_ms = duration_ms(connect_started.elapsed());
+    let mut timings = SqlTimings {
+        connect_ms,
+        query_ms: None,
+        validation_query_ms: None,
+        write_probe_ms: None,
+    };
+
+    let mut session = match session {
+        Ok(session) => session,
+        Err(e) => {
+            error!("Failed to connect to {}: {}", S::LABEL, e);
+            return SqlCheckResult {
+                timings: Some(timings),
+                error: Some(format!("Connection failed: {}", e)),
+                ..empty_result(&config)
+            };
+        }
+    };
+
+    debug!("Successfully connected to {}", S::LABEL);
+    let tls = session.tls_status().await;
+    let version = session.version().await;
+    let server = if config.server_status {
+        session.server_status().await
+    } else {
+        None
+    };
+
+    // Try to list tables of the configured schemas
+    let schemas = if config.schemas.is_empty() {
+        S::default_schemas(&config)
+    } else {
+        config.schemas.clone()
+    };
+    let query_started = Instant::now();
+    let tables_result = session.list_tables(&schemas).await;
+    timings.query_ms = Some(duration_ms(query_started.elapsed()));
+
+    let tables: Vec<String> = match tables_result {
+        Ok(rows) => rows
+            .into_iter()
+            .map(|(schema, name)| table_name(schema, name, &schemas))
+            .collect(),
+        Err(e) => {
+            error!("Failed to retrieve tables: {}", e);
+            return SqlCheckResult {
+                version,
+                server,
+                tls,
+                timings: Some(timings),
+                error: Some(format!("Failed to retrieve tables: {}", e)),
+                ..empty_result(&config)
+            };
+        }
+    };
+    info!("Successfully retrieved {} tables", tables.len());
+
+    // Compare the tables with the expected schema, if any
+    let schema = check_schema(&config, &tables, || session.list_columns(&schemas)).await;
+
+    // Run the validation query, if any
+    let query = match &config.query {
+        Some(sql) => {
+            let validation_started = Instant::now();
+            let query = session.run_query(sql, &config).await;
+            timings.validation_query_ms = Some(duration_ms(validation_started.elapsed()));
+            Some(query)
+        }
+        None => None,
+    };
+    let error = query.as_ref().and_then(|query| query.error.clone());
+    if let Some(error) = &error {
+        error!("Validation query failed: {}", error);
+    }
+
+    // Write a probe row, if enabled
+    let write_probe = if config.write_probe {
+        let probe_started = Instant::now();
+        let probe = session.run_write_probe(&config).await;
+        timings.write_probe_ms = Some(duration_ms(probe_started.elapsed()));
+        Some(probe)
+    } else {
+        None
+    };
+
+    // A schema mismatch comes first, it usually explains a failing query
+    let error = schema
+        .as_ref()
+        .and_then(|schema| schema.error.clone())
+        .or(error)
+        .or_else(|| write_probe.as_ref().and_then(|probe| probe.error.clone()));
+
+    SqlCheckResult {
+        success: error.is_none(),
+        version,
+        server,
+        tables: Some(tables),
+        tls,
+        schema,
+        query,
+        write_probe,
+        timings: Some(timings),
+        error,
+        ..empty_result(&config)
+    }
+}
+
+/// Check a SQL database connection and retrieve information
+#[instrument(skip(config), fields(identifier = %config.identifier, driver = %config.driver))]
+pub async fn check_sql(config: SqlConfig) -> SqlCheckResult {
+    info!("Checking SQL database: {}", config.identifier);
+
+    match find_driver(&config.driver) {
+        Some(driver) => (driver.check)(config).await,
+        None => {
+            error!("Unsupported SQL driver: {}", config.driver);
+            SqlCheck::error_result(
+                &config,
+                format!("Unsupported SQL driver: {}", config.driver),
+            )
+        }
+    }
+}
@@ -0,0 +1,552 @@
+use super::check_database;
+use super::driver::{SqlDriver, SqlSession};
+use super::probe::{ProbeStatements, WriteProbe, probe_token};
+use super::query::{add_row, complete, empty_query_result, table_name};
+use super::schema::TableColumn;
+use super::statement::{Lexicon, check_words, query_words};
+use crate::models::{SqlConfig, SqlQueryResult, SqlTlsMode, SqlTlsStatus, SqlWriteProbeResult};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use futures::TryStreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use tiberius::error::Error;
+use tiberius::{AuthMethod, Client, ColumnData, Config, EncryptionLevel, FromSql, QueryItem};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+use tracing::{debug, warn};
+
+/// Connection to a SQL Server
+type MsSqlClient = Client<Compat<TcpStream>>;
+
+/// Microsoft SQL Server driver, over the TDS protocol
+pub static MSSQL: SqlDriver = SqlDriver {
+    name: "mssql",
+    aliases: &["sqlserver"],
+    scheme: "mssql",
+    default_port: 1433,
+    default_user: "sa",
+    default_database: "master",
+    file_url: false,
+    client_certificate: false,
+    check_query: Some(check_read_only),
+    check: |config| Box::pin(check_database::<MsSqlClient>(config)),
+};
+
+impl SqlSession for MsSqlClient {
+    const LABEL: &'static str = "SQL Server";
+
+    async fn connect(config: &SqlConfig) -> Result<Self, String> {
+        connect(client_config(config))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn default_schemas(_config: &SqlConfig) -> Vec<String> {
+        vec!["dbo".to_string()]
+    }
+
+    async fn version(&mut self) -> Option<String> {
+        fetch_text(
+            self,
+            "SELECT CAST(SERVERPROPERTY('ProductVersion') AS NVARCHAR(128))",
+        )
+        .await
+    }
+
+    async fn tls_status(&mut self) -> Option<SqlTlsStatus> {
+        tls_status(self).await
+    }
+
+    async fn list_tables(&mut self, schemas: &[String]) -> Result<Vec<(String, String)>, String> {
+        list_tables(self, schemas).await.map_err(|e| e.to_string())
+    }
+
+    async fn list_columns(&mut self, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+        list_columns(self, schemas).await.map_err(|e| e.to_string())
+    }
+
+    async fn run_query(&mut self, sql: &str, config: &SqlConfig) -> SqlQueryResult {
+        run_validation_query(self, sql, config).await
+    }
+
+    async fn run_write_probe(&mut self, config: &SqlConfig) -> SqlWriteProbeResult {
+        run_write_probe(self, &config.probe_table).await
+    }
+}
+
+/// Client configuration of the check
+/// The TLS modes map to encryption levels, see [`encryption`]
+fn client_config(config: &SqlConfig) -> Config {
+    let mut client_config = Config::new();
+    client_config.host(&config.host);
+    client_config.port(config.port);
+    client_config.database(&config.database);
+    client_config.authentication(AuthMethod::sql_server(&config.user, &config.password));
+
+    for (name, value) in &config.options {
+        match name.as_str() {
+            "application_name" => client_config.application_name(value),
+            name => warn!("Ignoring unsupported SQL Server option '{}'", name),
+        }
+    }
+
+    let (level, verify) = encryption(config.tls_mode);
+    client_config.encryption(level);
+    if !verify {
+        client_config.trust_cert();
+    } else if let Some(ca) = &config.tls_ca {
+        // The CA is only used to verify the server certificate, which is otherwise verified
+        // against the system roots
+        client_config.trust_cert_ca(ca);
+    }
+
+    client_config
+}
+
+/// Encryption level of a TLS mode, and whether the server certificate is verified
+/// Without a mode, the connection is encrypted if the server supports it but the certificate is
+/// not verified, like `prefer` and the default of the other drivers, since SQL Server commonly
+/// uses a self-signed certificate
+fn encryption(mode: Option<SqlTlsMode>) -> (EncryptionLevel, bool) {
+    match mode {
+        Some(SqlTlsMode::Disable) => (EncryptionLevel::NotSupported, false),
+        Some(SqlTlsMode::Prefer) | None => (EncryptionLevel::On, false),
+        Some(SqlTlsMode::Require) => (EncryptionLevel::Required, false),
+        Some(SqlTlsMode::VerifyCa | SqlTlsMode::VerifyFull) => (EncryptionLevel::Required, true),
+    }
+}
+
+/// Connect to the server, following the redirection of Azure SQL to the node hosting the database
+async fn connect(config: Config) -> Result<MsSqlClient, Error> {
+    let tcp = TcpStream::connect(config.get_addr()).await?;
+    tcp.set_nodelay(true)?;
+
+    match Client::connect(config.clone(), tcp.compat_write()).await {
+        Err(Error::Routing { host, port }) => {
+            debug!("Redirected to {}:{}", host, port);
+            let mut config = config;
+            config.host(&host);
+            config.port(port);
+
+            let tcp = TcpStream::connect(config.get_addr()).await?;
+            tcp.set_nodelay(true)?;
+            Client::connect(config, tcp.compat_write()).await
+        }
+        result => result,
+    }
+}
+
+/// Execute a batch, discarding its results
+async fn execute(client: &mut MsSqlClient, sql: &str) -> Result<(), Error> {
+    client.simple_query(sql).await?.into_results().await?;
+    Ok(())
+}
+
+/// Fetch a single text value, e.g. the server version
+async fn fetch_text(client: &mut MsSqlClient, sql: &str) -> Option<String> {
+    let result = async { client.query(sql, &[]).await?.into_row().await }.await;
+
+    match result {
+        Ok(row) => row.and_then(|row| row.get::<&str, _>(0).map(String::from)),
+        Err(e) => {
+            warn!("Failed to run '{}': {}", sql, e);
+            None
+        }
+    }
+}
+
+/// Encryption of the current session, SQL Server does not expose its protocol or cipher
+async fn tls_status(client: &mut MsSqlClient) -> Option<SqlTlsStatus> {
+    fetch_text(
+        client,
+        "SELECT CAST(CONNECTIONPROPERTY('encrypt_option') AS NVARCHAR(8))",
+    )
+    .await
+    .map(|encrypted| SqlTlsStatus {
+        encrypted: encrypted.eq_ignore_ascii_case("true"),
+        version: None,
+        cipher: None,
+    })
+}
+
+/// Tables and views of the given schemas
+async fn list_tables(
+    client: &mut MsSqlClient,
+    schemas: &[String],
+) -> Result<Vec<(String, String)>, Error> {
+    let placeholders: Vec<String> = (1..=schemas.len()).map(|i| format!("@P{}", i)).collect();
+    let sql = format!(
+        "SELECT TABLE_SCHEMA, TABLE_NAME
+         FROM INFORMATION_SCHEMA.TABLES
+         WHERE TABLE_SCHEMA IN ({})
+         ORDER BY TABLE_SCHEMA, TABLE_NAME",
+        placeholders.join(", ")
+    );
+    let params: Vec<&dyn tiberius::ToSql> = schemas
+        .iter()
+        .map(|schema| schema as &dyn tiberius::ToSql)
+        .collect();
+
+    let rows = client
+        .query(sql, &params)
+        .await?
+        .into_first_result()
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let text = |index| row.get::<&str, _>(index).unwrap_or_default().to_string();
+            (text(0), text(1))
+        })
+        .collect())
+}
+
+/// Statements and clauses that write, end the transaction or run other code, which validation
+/// queries must not contain
+const WRITE_KEYWORDS: &[&str] = &[
+    "ALTER",
+    "BACKUP",
+    "BEGIN",
+    "BULK",
+    "CHECKPOINT",
+    "COMMIT",
+    "CREATE",
+    "DBCC",
+    "DELETE",
+    "DENY",
+    "DISABLE",
+    "DROP",
+    "ENABLE",
+    "EXEC",
+    "EXECUTE",
+    "GRANT",
+    "INSERT",
+    "INTO",
+    "KILL",
+    "MERGE",
+    "OPENDATASOURCE",
+    "OPENQUERY",
+    "OPENROWSET",
+    "RECEIVE",
+    "RECONFIGURE",
+    "RESTORE",
+    "REVOKE",
+    "ROLLBACK",
+    "SAVE",
+    "SEND",
+    "SHUTDOWN",
+    "TRUNCATE",
+    "UPDATE",
+    "UPDATETEXT",
+    "WRITETEXT",
+];
+
+/// Quotes and comments of T-SQL
+const TSQL: Lexicon = Lexicon {
+    quotes: &[('\'', '\''), ('"', '"'), ('[', ']')],
+    backslash_escapes: false,
+    spaced_dash_comments: false,
+    nested_comments: true,
+    hash_comments: false,
+    executable_comments: false,
+};
+
+/// Reject a validation query that is not a single `SELECT` (or `WITH ... SELECT`) statement
+/// SQL Server has no read-only transactions, and runs every statement of the text sent with
+/// sp_executesql, even without separators, so a `COMMIT` would persist the writes before them
+/// Comments, string literals and quoted identifiers are skipped, so `[update]` or `'DELETE'`
+/// are accepted
+pub fn check_read_only(sql: &str) -> Result<(), String> {
+    let words = query_words(sql, &TSQL)?;
+    check_words(&words, "SQL Server", &["SELECT", "WITH"], WRITE_KEYWORDS)?;
+
+    // NEXT VALUE FOR increments a sequence, which is not rolled back
+    if words
+        .windows(3)
+        .any(|window| window == ["NEXT", "VALUE", "FOR"])
+    {
+        return Err(
+            "Only read-only queries are accepted by SQL Server, found NEXT VALUE FOR".to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Run the validation query of the check in a transaction, which is rolled back, and compare
+/// its result with the expectations
+/// SQL Server has no read-only transactions, so queries that could write are rejected before
+/// they are sent (see [`check_read_only`]), and the user of the check should only be granted
+/// read permissions
+async fn run_validation_query(
+    client: &mut MsSqlClient,
+    sql: &str,
+    config: &SqlConfig,
+) -> SqlQueryResult {
+    let mut result = empty_query_result();
+
+    if let Err(e) = check_read_only(sql) {
+        return complete(result, Err(format!("Query rejected: {}", e)), config);
+    }
+
+    if let Err(e) = execute(client, "BEGIN TRANSACTION").await {
+        let error = format!("Failed to start a transaction: {}", e);
+        return complete(result, Err(error), config);
+    }
+
+    // Executed with sp_executesql, only the first result set is kept
+    let fetched = async {
+        let mut stream = client.query(sql, &[]).await?;
+        if let Some(columns) = stream.columns().await? {
+            result.columns = columns
+                .iter()
+                .map(|column| column.name().to_string())
+                .collect();
+        }
+
+        while let Some(item) = stream.try_next().await? {
+            if let QueryItem::Row(row) = item
+                && row.result_index() == 0
+            {
+                add_row(&mut result, config.max_rows, || {
+                    row.cells().map(|(_, data)| column_value(data)).collect()
+                });
+            }
+        }
+
+        Ok::<_, Error>(())
+    }
+    .await
+    .map_err(|e| format!("Query failed: {}", e));
+
+    // The connection is closed after the check, rolling back is only good manners
+    let _ = execute(client, "IF @@TRANCOUNT > 0 ROLLBACK").await;
+
+    complete(result, fetched, config)
+}
+
+/// Columns of the tables of the given schemas, with their type (e.g. `nvarchar`)
+async fn list_columns(
+    client: &mut MsSqlClient,
+    schemas: &[String],
+) -> Result<Vec<TableColumn>, Error> {
+    let placeholders: Vec<String> = (1..=schemas.len()).map(|i| format!("@P{}", i)).collect();
+    let sql = format!(
+        "SELECT TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME, DATA_TYPE
+         FROM INFORMATION_SCHEMA.COLUMNS
+         WHERE TABLE_SCHEMA IN ({})
+         ORDER BY TABLE_SCHEMA, TABLE_NAME, ORDINAL_POSITION",
+        placeholders.join(", ")
+    );
+    let params: Vec<&dyn tiberius::ToSql> = schemas
+        .iter()
+        .map(|schema| schema as &dyn tiberius::ToSql)
+        .collect();
+
+    let rows = client
+        .query(sql, &params)
+        .await?
+        .into_first_result()
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let text = |index| row.get::<&str, _>(index).unwrap_or_default().to_string();
+            TableColumn {
+                table: table_name(text(0), text(1), schemas),
+                name: text(2),
+                types: vec![text(3)],
+            }
+        })
+        .collect())
+}
+
+/// Create the probe table if absent, then insert a row with a unique token, read it back and
+/// delete it in a transaction, which is committed so that the whole write path is exercised
+async fn run_write_probe(client: &mut MsSqlClient, table: &str) -> SqlWriteProbeResult {
+    let statements = ProbeStatements::new(
+        table,
+        format!(
+            "IF OBJECT_ID(N'{0}', N'U') IS NULL
+             CREATE TABLE {0} (
+                 token NVARCHAR(255) PRIMARY KEY,
+                 written_at DATETIME2 NOT NULL DEFAULT SYSUTCDATETIME()
+             )",
+            table
+        ),
+        "@P1",
+    );
+    let token = probe_token();
+    let mut probe = WriteProbe::new(table);
+
+    if probe
+        .step("create_table", execute(client, &statements.create_table))
+        .await
+        .is_none()
+        || probe
+            .step("begin", execute(client, "BEGIN TRANSACTION"))
+            .await
+            .is_none()
+    {
+        return probe.finish();
+    }
+
+    let completed = probe
+        .step(
+            "insert",
+            client.execute(statements.insert.as_str(), &[&token]),
+        )
+        .await
+        .is_some()
+        && probe
+            .step("select", async {
+                let row = async {
+                    client
+                        .query(statements.select.as_str(), &[&token])
+                        .await?
+                        .into_row()
+                        .await
+                }
+                .await
+                .map_err(|e| e.to_string())?;
+
+                row.map(|_| ())
+                    .ok_or_else(|| "the inserted row was not found".to_string())
+            })
+            .await
+            .is_some()
+        && probe
+            .step(
+                "delete",
+                client.execute(statements.delete.as_str(), &[&token]),
+            )
+            .await
+            .is_some();
+
+    if completed {
+        probe.step("commit", execute(client, "COMMIT")).await;
+    } else {
+        let _ = execute(client, "IF @@TRANCOUNT > 0 ROLLBACK").await;
+    }
+
+    probe.finish()
+}
+
+/// Decode a column of the validation query into JSON
+/// Decimals are shown as strings to keep their precision, binary and XML values by type name
+fn column_value(data: &ColumnData<'static>) -> Value {
+    fn json<'a, T: FromSql<'a> + Serialize>(data: &'a ColumnData<'static>) -> Value {
+        T::from_sql(data)
+            .ok()
+            .flatten()
+            .map(|value| serde_json::to_value(value).unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    match data {
+        ColumnData::U8(value) => (*value).into(),
+        ColumnData::I16(value) => (*value).into(),
+        ColumnData::I32(value) => (*value).into(),
+        ColumnData::I64(value) => (*value).into(),
+        ColumnData::F32(value) => (*value).into(),
+        ColumnData::F64(value) => (*value).into(),
+        ColumnData::Bit(value) => (*value).into(),
+        ColumnData::String(value) => value.as_deref().into(),
+        ColumnData::Guid(value) => value.map(|guid| guid.to_string()).into(),
+        ColumnData::Numeric(value) => value.map(|numeric| numeric.to_string()).into(),
+        ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+            json::<NaiveDateTime>(data)
+        }
+        ColumnData::DateTimeOffset(_) => json::<DateTime<Utc>>(data),
+        ColumnData::Date(_) => json::<NaiveDate>(data),
+        ColumnData::Time(_) => json::<NaiveTime>(data),
+        ColumnData::Binary(None) | ColumnData::Xml(None) => Value::Null,
+        ColumnData::Binary(Some(_)) => Value::from("<BINARY>"),
+        ColumnData::Xml(Some(_)) => Value::from("<XML>"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::borrow::Cow;
+    use tiberius::numeric::Numeric;
+
+    #[test]
+    fn test_encryption_defaults_to_prefer() {
+        assert_eq!(encryption(None), encryption(Some(SqlTlsMode::Prefer)));
+        assert_eq!(encryption(None), (EncryptionLevel::On, false));
+        assert_eq!(
+            encryption(Some(SqlTlsMode::Require)),
+            (EncryptionLevel::Required, false)
+        );
+        assert_eq!(
+            encryption(Some(SqlTlsMode::VerifyFull)),
+            (EncryptionLevel::Required, true)
+        );
+    }
+
+    #[test]
+    fn test_check_read_only() {
+        for sql in [
+            "SELECT 1",
+            "select count(*) from orders;",
+            "WITH recent AS (SELECT * FROM orders) SELECT * FROM recent",
+            "SELECT [update], 'DELETE FROM t; COMMIT' AS note FROM [insert] -- COMMIT",
+            "SELECT 1; /* DROP TABLE orders */",
+            "SELECT /* a /* nested */ COMMIT */ 1",
+            "SELECT 'it''s' AS quoted, \"odd \"\"name\"\"\" FROM t",
+            "SELECT * FROM orders ORDER BY id OFFSET 0 ROWS FETCH NEXT 5 ROWS ONLY",
+            "SELECT updated_at, created_by FROM orders",
+        ] {
+            assert_eq!(check_read_only(sql), Ok(()), "{}", sql);
+        }
+
+        for sql in [
+            "DELETE FROM orders; COMMIT",
+            "SELECT 1; COMMIT",
+            "SELECT 1; SELECT 2",
+            "SELECT 1 DELETE FROM orders",
+            "SELECT 1 COMMIT",
+            "SELECT * INTO orders_copy FROM orders",
+            "WITH gone AS (SELECT * FROM orders) DELETE FROM gone",
+            "EXEC sp_who",
+            "SELECT NEXT VALUE FOR order_ids",
+            "SELECT * FROM OPENQUERY(remote, 'DELETE FROM t')",
+            "SELECT 'unterminated",
+            "-- only a comment",
+            "",
+        ] {
+            assert!(check_read_only(sql).is_err(), "{}", sql);
+        }
+
+        assert_eq!(
+            check_read_only("SELECT 1 commit").unwrap_err(),
+            "Only read-only queries are accepted by SQL Server, found COMMIT"
+        );
+    }
+
+    #[test]
+    fn test_column_value() {
+        assert_eq!(column_value(&ColumnData::I32(Some(42))), json!(42));
+        assert_eq!(column_value(&ColumnData::I64(None)), Value::Null);
+        assert_eq!(column_value(&ColumnData::Bit(Some(true))), json!(true));
+        assert_eq!(
+            column_value(&ColumnData::String(Some(Cow::Borrowed("ok")))),
+            json!("ok")
+        );
+        assert_eq!(
+            column_value(&ColumnData::Numeric(Some(Numeric::new_with_scale(
+                12345, 2
+            )))),
+            json!("123.45")
+        );
+        assert_eq!(
+            column_value(&ColumnData::Binary(Some(Cow::Borrowed(&[0u8][..])))),
+            json!("<BINARY>")
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use super::driver::{SqlDriver, SqlSession};
+use super::probe::{ProbeStatements, run_write_probe};
+use super::query::{
+    complete, decode_as, empty_query_result, fetch_scalar, run_validation_query, table_name,
+    unsupported_type,
+};
+use super::schema::TableColumn;
+use super::statement::{Lexicon, check_words, query_words};
+use super::{check_database, connection_url};
+use crate::models::{
+    SqlConfig, SqlQueryResult, SqlReplicationRole, SqlServerStatus, SqlTlsMode, SqlTlsStatus,
+    SqlWriteProbeResult,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde_json::Value;
+use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlRow, MySqlSslMode};
+use sqlx::{Column, Executor, MySql, MySqlPool, Row, TypeInfo};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::warn;
+
+/// MySQL (and MariaDB) driver
+pub static MYSQL: SqlDriver = SqlDriver {
+    name: "mysql",
+    aliases: &["mariadb"],
+    scheme: "mysql",
+    default_port: 3306,
+    default_user: "root",
+    default_database: "mysql",
+    file_url: false,
+    client_certificate: true,
+    check_query: Some(check_read_only),
+    check: |config| Box::pin(check_database::<MySqlPool>(config)),
+};
+
+impl SqlSession for MySqlPool {
+    const LABEL: &'static str = "MySQL";
+
+    async fn connect(config: &SqlConfig) -> Result<Self, String> {
+        let options = MySqlConnectOptions::from_str(&connection_url(&MYSQL, config))
+            .map_err(|e| e.to_string())?;
+
+        MySqlPoolOptions::new()
+            .max_connections(1)
+            .connect_with(with_tls(options, config))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn default_schemas(config: &SqlConfig) -> Vec<String> {
+        vec![config.database.clone()]
+    }
+
+    async fn version(&mut self) -> Option<String> {
+        fetch_scalar(self, "SELECT VERSION()").await
+    }
+
+    async fn tls_status(&mut self) -> Option<SqlTlsStatus> {
+        tls_status(self).await
+    }
+
+    async fn server_status(&mut self) -> Option<SqlServerStatus> {
+        Some(server_status(self).await)
+    }
+
+    async fn list_tables(&mut self, schemas: &[String]) -> Result<Vec<(String, String)>, String> {
+        let placeholders = vec!["?"; schemas.len()].join(", ");
+        let sql = format!(
+            "SELECT table_schema, table_name
+             FROM information_schema.tables
+             WHERE table_schema IN ({})
+             ORDER BY table_schema, table_name",
+            placeholders
+        );
+
+        schemas
+            .iter()
+            .fold(
+                sqlx::query_as::<_, (String, String)>(&sql),
+                |query, schema| query.bind(schema),
+            )
+            .fetch_all(&*self)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn list_columns(&mut self, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+        list_columns(self, schemas).await
+    }
+
+    async fn run_query(&mut self, sql: &str, config: &SqlConfig) -> SqlQueryResult {
+        if let Err(e) = check_read_only(sql) {
+            let error = format!("Query rejected: {}", e);
+            return complete(empty_query_result(), Err(error), config);
+        }
+
+        run_validation_query(
+            self,
+            "START TRANSACTION READ ONLY",
+            sql,
+            config,
+            column_value,
+        )
+        .await
+    }
+
+    async fn run_write_probe(&mut self, config: &SqlConfig) -> SqlWriteProbeResult {
+        let table = &config.probe_table;
+        run_write_probe(self, table, &probe_statements(table)).await
+    }
+}
+
+/// Quotes and comments of MySQL
+const MYSQL_SQL: Lexicon = Lexicon {
+    quotes: &[('\'', '\''), ('"', '"'), ('`', '`')],
+    backslash_escapes: true,
+    spaced_dash_comments: true,
+    nested_comments: false,
+    hash_comments: true,
+    executable_comments: true,
+};
+
+/// Statements that only read, which validation queries must start with
+const READ_STATEMENTS: &[&str] = &["SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "EXPLAIN"];
+
+/// Clauses that write or end the transaction within a read statement (e.g. `INTO OUTFILE`, or
+/// `WITH ... DELETE`), which validation queries must not contain
+const WRITE_KEYWORDS: &[&str] = &["COMMIT", "DELETE", "INSERT", "INTO", "ROLLBACK", "UPDATE"];
+
+/// Reject a validation query that is not a single read statement
+/// MySQL commits implicitly before DDL (e.g. `DROP TABLE`), which would run despite the read-only
+/// transaction, and executes the content of `/*! ... */` comments
+/// Comments, string literals and quoted identifiers are skipped, so `` `update` `` or `'DELETE'`
+/// are accepted
+pub fn check_read_only(sql: &str) -> Result<(), String> {
+    let words = query_words(sql, &MYSQL_SQL)?;
+    check_words(&words, "MySQL", READ_STATEMENTS, WRITE_KEYWORDS)
+}
+
+/// Apply the TLS settings of the check, which override the TLS options of the URL
+fn with_tls(mut options: MySqlConnectOptions, config: &SqlConfig) -> MySqlConnectOptions {
+    if let Some(mode) = config.tls_mode {
+        options = options.ssl_mode(match mode {
+            SqlTlsMode::Disable => MySqlSslMode::Disabled,
+            SqlTlsMode::Prefer => MySqlSslMode::Preferred,
+            SqlTlsMode::Require => MySqlSslMode::Required,
+            SqlTlsMode::VerifyCa => MySqlSslMode::VerifyCa,
+            SqlTlsMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+        });
+    }
+
+    if let Some(ca) = &config.tls_ca {
+        options = options.ssl_ca(ca);
+    }
+
+    if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+        options = options.ssl_client_cert(cert).ssl_client_key(key);
+    }
+
+    options
+}
+
+/// Encryption of the current session, from the Ssl_version and Ssl_cipher status variables,
+/// which are empty when the session is not encrypted
+async fn tls_status(pool: &MySqlPool) -> Option<SqlTlsStatus> {
+    let result = sqlx::query_as::<_, (String, String)>(
+        "SHOW SESSION STATUS WHERE Variable_name IN ('Ssl_version', 'Ssl_cipher')",
+    )
+    .fetch_all(pool)
+    .await;
+
+    match result {
+        Ok(rows) => {
+            let status: HashMap<String, String> = rows
+                .into_iter()
+                .filter(|(_, value)| !value.is_empty())
+                .collect();
+            let version = status.get("Ssl_version").cloned();
+
+            Some(SqlTlsStatus {
+                encrypted: version.is_some(),
+                version,
+                cipher: status.get("Ssl_cipher").cloned(),
+            })
+        }
+        Err(e) => {
+            warn!("Failed to retrieve the TLS status: {}", e);
+            None
+        }
+    }
+}
+
+/// Current user, privileges, max_connections, database size and read-only setting, in a
+/// single round trip
+/// Privileges are the global privileges and the privileges on the database granted to the
+/// current account, whose grantee is quoted as `'user'@'host'` (the user may contain `@`)
+const SERVER_STATUS: &str = "SELECT
+         account.name,
+         (
+             SELECT GROUP_CONCAT(DISTINCT granted.PRIVILEGE_TYPE ORDER BY granted.PRIVILEGE_TYPE)
+             FROM (
+                 SELECT GRANTEE, PRIVILEGE_TYPE FROM information_schema.USER_PRIVILEGES
+                 UNION ALL
+                 SELECT GRANTEE, PRIVILEGE_TYPE FROM information_schema.SCHEMA_PRIVILEGES
+                 WHERE TABLE_SCHEMA = DATABASE()
+             ) AS granted
+             WHERE granted.GRANTEE = account.grantee AND granted.PRIVILEGE_TYPE <> 'USAGE'
+         ),
+         CAST(@@max_connections AS UNSIGNED),
+         (
+             SELECT CAST(COALESCE(SUM(data_length + index_length), 0) AS UNSIGNED)
+             FROM information_schema.TABLES
+             WHERE table_schema = DATABASE()
+         ),
+         CAST(@@global.read_only AS SIGNED)
+     FROM (
+         SELECT
+             CURRENT_USER() AS name,
+             CONCAT(
+                 '''',
+                 LEFT(
+                     CURRENT_USER(),
+                     CHAR_LENGTH(CURRENT_USER())
+                         - CHAR_LENGTH(SUBSTRING_INDEX(CURRENT_USER(), '@', -1)) - 1
+                 ),
+                 '''@''',
+                 SUBSTRING_INDEX(CURRENT_USER(), '@', -1),
+                 ''''
+             ) AS grantee
+     ) AS account";
+
+/// Row of [`SERVER_STATUS`], privileges are comma-separated
+type ServerStatusRow = (
+    Option<String>,
+    Option<String>,
+    Option<u64>,
+    Option<u64>,
+    Option<i64>,
+);
+
+/// State of the server in three round trips, since the connection count and the replica status
+/// are only available through SHOW statements
+/// Each round trip is independent so that a missing privilege only hides what it protects
+async fn server_status(pool: &MySqlPool) -> SqlServerStatus {
+    let mut status = sqlx::query_as::<_, ServerStatusRow>(SERVER_STATUS)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|e| warn!("Failed to retrieve the server status: {}", e))
+        .map(server_status_from_row)
+        .unwrap_or_default();
+
+    status.connections = sqlx::query_as::<_, (String, String)>(
+        "SHOW GLOBAL STATUS WHERE Variable_name = 'Threads_connected'",
+    )
+    .fetch_optional(pool)
+    .await
+    .inspect_err(|e| warn!("Failed to retrieve the connection count: {}", e))
+    .ok()
+    .flatten()
+    .and_then(|(_, count)| count.parse().ok());
+
+    (status.role, status.replica_lag_seconds) = replication(pool).await;
+
+    status
+}
+
+fn server_status_from_row(row: ServerStatusRow) -> SqlServerStatus {
+    let (current_user, privileges, max_connections, database_size_bytes, read_only) = row;
+
+    SqlServerStatus {
+        current_user,
+        // GROUP_CONCAT returns NULL without any privilege
+        privileges: Some(
+            privileges
+                .iter()
+                .flat_map(|privileges| privileges.split(','))
+                .map(String::from)
+                .collect(),
+        ),
+        max_connections,
+        database_size_bytes,
+        read_only: read_only.map(|read_only| read_only != 0),
+        ..SqlServerStatus::default()
+    }
+}
+
+/// Replication role and lag, from the replica status (which requires the REPLICATION CLIENT
+/// privilege)
+async fn replication(pool: &MySqlPool) -> (Option<SqlReplicationRole>, Option<f64>) {
+    // SHOW REPLICA STATUS replaced SHOW SLAVE STATUS in MySQL 8.0.22, MariaDB only has the latter
+    let mut result = pool.fetch_all("SHOW REPLICA STATUS").await;
+    if result.is_err() {
+        result = pool.fetch_all("SHOW SLAVE STATUS").await;
+    }
+
+    match result {
+        Ok(rows) => {
+            let lags: Vec<Value> = rows.iter().map(replica_lag).collect();
+            replication_from_lags(&lags)
+        }
+        Err(e) => {
+            warn!("Failed to retrieve the replica status: {}", e);
+            (None, None)
+        }
+    }
+}
+
+/// Lag of a replica status row, NULL while replication is stopped
+fn replica_lag(row: &MySqlRow) -> Value {
+    row.columns()
+        .iter()
+        .position(|column| {
+            matches!(
+                column.name(),
+                "Seconds_Behind_Source" | "Seconds_Behind_Master"
+            )
+        })
+        .map_or(Value::Null, |index| column_value(row, index))
+}
+
+/// Replication role and lag from the lag of every replica status row: a server without replica
+/// status is a primary, and multi-source replicas have a row per source, the most delayed one
+/// is reported
+fn replication_from_lags(lags: &[Value]) -> (Option<SqlReplicationRole>, Option<f64>) {
+    if lags.is_empty() {
+        return (Some(SqlReplicationRole::Primary), None);
+    }
+
+    let lag = lags
+        .iter()
+        .filter_map(|lag| {
+            lag.as_f64()
+                .or_else(|| lag.as_str().and_then(|lag| lag.parse().ok()))
+        })
+        .reduce(f64::max);
+
+    (Some(SqlReplicationRole::Replica), lag)
+}
+
+/// Columns of the tables of the given schemas, with their type with and without its length
+/// (e.g. `varchar` and `varchar(255)`)
+async fn list_columns(pool: &MySqlPool, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+    let placeholders = vec!["?"; schemas.len()].join(", ");
+    let sql = format!(
+        "SELECT TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME, DATA_TYPE, COLUMN_TYPE
+         FROM information_schema.COLUMNS
+         WHERE TABLE_SCHEMA IN ({})
+         ORDER BY TABLE_SCHEMA, TABLE_NAME, ORDINAL_POSITION",
+        placeholders
+    );
+
+    let rows = schemas
+        .iter()
+        .fold(
+            sqlx::query_as::<_, (String, String, String, String, String)>(&sql),
+            |query, schema| query.bind(schema),
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(schema, table, name, data_type, column_type)| TableColumn {
+                table: table_name(schema, table, schemas),
+                name,
+                types: vec![data_type, column_type],
+            },
+        )
+        .collect())
+}
+
+/// Write probe statements in the MySQL dialect
+fn probe_statements(table: &str) -> ProbeStatements {
+    let create_table = format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+             token VARCHAR(255) PRIMARY KEY,
+             written_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+         )",
+        table
+    );
+
+    ProbeStatements::new(table, create_table, "?")
+}
+
+/// Decode a column of the validation query into JSON, by column type since integer types are
+/// interchangeable in sqlx
+/// Types without a JSON equivalent (e.g. BLOB) are shown by name
+fn column_value(row: &MySqlRow, index: usize) -> Value {
+    let type_name = row.columns()[index].type_info().name();
+
+    let value = match type_name {
+        "BOOLEAN" => decode_as::<MySql, bool>(row, index),
+        name if name.ends_with("INT UNSIGNED") => decode_as::<MySql, u64>(row, index),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => {
+            decode_as::<MySql, i64>(row, index)
+        }
+        "FLOAT" => decode_as::<MySql, f32>(row, index),
+        "DOUBLE" => decode_as::<MySql, f64>(row, index),
+        // Decimals are sent as text, shown as strings to keep their precision
+        "DECIMAL" => row
+            .try_get_unchecked::<Option<String>, _>(index)
+            .ok()
+            .map(Value::from),
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
+            decode_as::<MySql, String>(row, index)
+        }
+        "JSON" => decode_as::<MySql, Value>(row, index),
+        "TIMESTAMP" => decode_as::<MySql, DateTime<Utc>>(row, index),
+        "DATETIME" => decode_as::<MySql, NaiveDateTime>(row, index),
+        "DATE" => decode_as::<MySql, NaiveDate>(row, index),
+        "TIME" => decode_as::<MySql, NaiveTime>(row, index),
+        "NULL" => Some(Value::Null),
+        _ => None,
+    };
+
+    value.unwrap_or_else(|| unsupported_type(row, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Check;
+    use crate::check::sql::SqlCheck;
+    use serde_json::json;
+
+    #[test]
+    fn test_server_status_from_row() {
+        let status = server_status_from_row((
+            Some("app@%".to_string()),
+            Some("INSERT,SELECT".to_string()),
+            Some(151),
+            Some(16384),
+            Some(1),
+        ));
+
+        assert_eq!(status.current_user.as_deref(), Some("app@%"));
+        assert_eq!(
+            status.privileges,
+            Some(vec!["INSERT".to_string(), "SELECT".to_string()])
+        );
+        assert_eq!(status.max_connections, Some(151));
+        assert_eq!(status.database_size_bytes, Some(16384));
+        assert_eq!(status.read_only, Some(true));
+
+        // GROUP_CONCAT is NULL when nothing is granted besides USAGE
+        let status = server_status_from_row((Some("app@%".to_string()), None, None, None, Some(0)));
+        assert_eq!(status.privileges, Some(Vec::new()));
+        assert_eq!(status.read_only, Some(false));
+    }
+
+    #[test]
+    fn test_replication_from_lags() {
+        let (role, lag) = replication_from_lags(&[]);
+        assert!(matches!(role, Some(SqlReplicationRole::Primary)));
+        assert_eq!(lag, None);
+
+        // The most delayed source is reported, stopped sources have no lag
+        let (role, lag) = replication_from_lags(&[json!(3), Value::Null, json!("12")]);
+        assert!(matches!(role, Some(SqlReplicationRole::Replica)));
+        assert_eq!(lag, Some(12.0));
+
+        let (role, lag) = replication_from_lags(&[Value::Null]);
+        assert!(matches!(role, Some(SqlReplicationRole::Replica)));
+        assert_eq!(lag, None);
+    }
+
+    #[test]
+    fn test_check_read_only() {
+        for sql in [
+            "SELECT 1",
+            "select count(*) from orders;",
+            "WITH recent AS (SELECT * FROM orders) SELECT * FROM recent",
+            "SELECT `update`, 'DELETE FROM t; COMMIT' AS note FROM `insert` -- COMMIT",
+            "SELECT 'it\\'s', \"say \\\"drop\\\"\" # DROP TABLE orders",
+            "SELECT 1--1",
+            "SHOW TABLES",
+            "DESCRIBE orders",
+            "EXPLAIN SELECT * FROM orders",
+        ] {
+            assert_eq!(check_read_only(sql), Ok(()), "{}", sql);
+        }
+
+        for sql in [
+            "DROP TABLE users",
+            "TRUNCATE orders",
+            "ALTER TABLE orders ADD note TEXT",
+            "CREATE TABLE copy SELECT * FROM orders",
+            "SELECT 1; DROP TABLE users",
+            "SELECT 1 /*!, (DROP TABLE users) */",
+            "SELECT * FROM orders INTO OUTFILE '/tmp/orders'",
+            "WITH gone AS (SELECT id FROM orders) DELETE FROM orders",
+            "SELECT 'it\\' DROP TABLE users",
+            "",
+        ] {
+            assert!(check_read_only(sql).is_err(), "{}", sql);
+        }
+
+        assert_eq!(
+            check_read_only("DROP TABLE users").unwrap_err(),
+            "Only SELECT/WITH/SHOW/DESCRIBE/DESC/EXPLAIN queries are accepted by MySQL, found DROP"
+        );
+    }
+
+    // The pool connects lazily to a closed port, so a query reaching the server would fail to
+    // connect instead
+    #[tokio::test]
+    async fn test_ddl_query_is_rejected_before_the_server() {
+        let mut pool = MySqlPoolOptions::new()
+            .connect_lazy("mysql://root@127.0.0.1:1/app")
+            .unwrap();
+        let config = SqlCheck::parse_config(
+            "DB",
+            &HashMap::from([
+                ("driver".to_string(), "mysql".to_string()),
+                ("query".to_string(), "DROP TABLE users".to_string()),
+            ]),
+        )
+        .unwrap();
+
+        let result = pool.run_query("DROP TABLE users", &config).await;
+        assert!(!result.success);
+        assert_eq!(
+            result.error.as_deref(),
+            Some(
+                "Query rejected: Only SELECT/WITH/SHOW/DESCRIBE/DESC/EXPLAIN queries are accepted by MySQL, found DROP"
+            )
+        );
+    }
+
+    #[test]
+    fn test_server_status_can_be_disabled() {
+        let params = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let config = SqlCheck::parse_config("DB", &params(&[("driver", "mysql")])).unwrap();
+        assert!(config.server_status);
+
+        let config = SqlCheck::parse_config(
+            "DB",
+            &params(&[("driver", "mysql"), ("server_status", "false")]),
+        )
+        .unwrap();
+        assert!(!config.server_status);
+    }
+}
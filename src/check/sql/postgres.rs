@@ -0,0 +1,316 @@
+use super::driver::{SqlDriver, SqlSession};
+use super::probe::{ProbeStatements, run_write_probe};
+use super::query::{decode_as, fetch_scalar, run_validation_query, table_name, unsupported_type};
+use super::schema::TableColumn;
+use super::{check_database, connection_url};
+use crate::models::{
+    SqlConfig, SqlQueryResult, SqlReplicationRole, SqlServerStatus, SqlTlsMode, SqlTlsStatus,
+    SqlWriteProbeResult,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgRow, PgSslMode};
+use sqlx::types::Uuid;
+use sqlx::{PgPool, Postgres, Row};
+use std::str::FromStr;
+use tracing::warn;
+
+/// PostgreSQL driver
+pub static POSTGRES: SqlDriver = SqlDriver {
+    name: "postgres",
+    aliases: &["postgresql"],
+    scheme: "postgres",
+    default_port: 5432,
+    default_user: "postgres",
+    default_database: "postgres",
+    file_url: false,
+    client_certificate: true,
+    check_query: None,
+    check: |config| Box::pin(check_database::<PgPool>(config)),
+};
+
+impl SqlSession for PgPool {
+    const LABEL: &'static str = "PostgreSQL";
+
+    async fn connect(config: &SqlConfig) -> Result<Self, String> {
+        let options = PgConnectOptions::from_str(&connection_url(&POSTGRES, config))
+            .map_err(|e| e.to_string())?;
+
+        PgPoolOptions::new()
+            .max_connections(1)
+            .connect_with(with_tls(options, config))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn default_schemas(_config: &SqlConfig) -> Vec<String> {
+        vec!["public".to_string()]
+    }
+
+    async fn version(&mut self) -> Option<String> {
+        fetch_scalar(self, "SELECT current_setting('server_version')").await
+    }
+
+    async fn tls_status(&mut self) -> Option<SqlTlsStatus> {
+        tls_status(self).await
+    }
+
+    async fn server_status(&mut self) -> Option<SqlServerStatus> {
+        server_status(self).await
+    }
+
+    async fn list_tables(&mut self, schemas: &[String]) -> Result<Vec<(String, String)>, String> {
+        sqlx::query_as::<_, (String, String)>(
+            "SELECT table_schema, table_name
+             FROM information_schema.tables
+             WHERE table_schema = ANY($1)
+             ORDER BY table_schema, table_name",
+        )
+        .bind(schemas)
+        .fetch_all(&*self)
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    async fn list_columns(&mut self, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+        list_columns(self, schemas).await
+    }
+
+    async fn run_query(&mut self, sql: &str, config: &SqlConfig) -> SqlQueryResult {
+        run_validation_query(self, "BEGIN READ ONLY", sql, config, column_value).await
+    }
+
+    async fn run_write_probe(&mut self, config: &SqlConfig) -> SqlWriteProbeResult {
+        let table = &config.probe_table;
+        run_write_probe(self, table, &probe_statements(table)).await
+    }
+}
+
+/// Apply the TLS settings of the check, which override the TLS options of the URL
+fn with_tls(mut options: PgConnectOptions, config: &SqlConfig) -> PgConnectOptions {
+    if let Some(mode) = config.tls_mode {
+        options = options.ssl_mode(match mode {
+            SqlTlsMode::Disable => PgSslMode::Disable,
+            SqlTlsMode::Prefer => PgSslMode::Prefer,
+            SqlTlsMode::Require => PgSslMode::Require,
+            SqlTlsMode::VerifyCa => PgSslMode::VerifyCa,
+            SqlTlsMode::VerifyFull => PgSslMode::VerifyFull,
+        });
+    }
+
+    if let Some(ca) = &config.tls_ca {
+        options = options.ssl_root_cert(ca);
+    }
+
+    if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+        options = options.ssl_client_cert(cert).ssl_client_key(key);
+    }
+
+    options
+}
+
+/// Encryption of the current session, from pg_stat_ssl
+async fn tls_status(pool: &PgPool) -> Option<SqlTlsStatus> {
+    let result = sqlx::query_as::<_, (bool, Option<String>, Option<String>)>(
+        "SELECT ssl, version, cipher FROM pg_stat_ssl WHERE pid = pg_backend_pid()",
+    )
+    .fetch_one(pool)
+    .await;
+
+    match result {
+        Ok((encrypted, version, cipher)) => Some(SqlTlsStatus {
+            encrypted,
+            version,
+            cipher,
+        }),
+        Err(e) => {
+            warn!("Failed to retrieve the TLS status: {}", e);
+            None
+        }
+    }
+}
+
+/// State of the server in a single round trip: current user, privileges on the database,
+/// client connections, max_connections, database size, read-only setting, recovery and
+/// replay lag
+/// Only pg_database_size needs a privilege (CONNECT, or pg_read_all_stats), so it is guarded
+/// to leave the rest readable
+const SERVER_STATUS: &str = "SELECT
+         current_user::text,
+         ARRAY(
+             SELECT privilege
+             FROM unnest(ARRAY['CONNECT', 'CREATE', 'TEMPORARY']) AS privilege
+             WHERE has_database_privilege(current_database(), privilege)
+         ),
+         (SELECT count(*) FROM pg_stat_activity WHERE backend_type = 'client backend'),
+         current_setting('max_connections')::bigint,
+         CASE
+             WHEN has_database_privilege(current_database(), 'CONNECT')
+                 OR pg_has_role('pg_read_all_stats', 'MEMBER')
+             THEN pg_database_size(current_database())
+         END,
+         current_setting('transaction_read_only') = 'on',
+         pg_is_in_recovery(),
+         EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::float8";
+
+/// Row of [`SERVER_STATUS`]
+type ServerStatusRow = (
+    Option<String>,
+    Option<Vec<String>>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<bool>,
+    Option<bool>,
+    Option<f64>,
+);
+
+/// State of the server, `None` when it cannot be retrieved
+async fn server_status(pool: &PgPool) -> Option<SqlServerStatus> {
+    sqlx::query_as::<_, ServerStatusRow>(SERVER_STATUS)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|e| warn!("Failed to retrieve the server status: {}", e))
+        .ok()
+        .map(server_status_from_row)
+}
+
+fn server_status_from_row(row: ServerStatusRow) -> SqlServerStatus {
+    let (
+        current_user,
+        privileges,
+        connections,
+        max_connections,
+        database_size_bytes,
+        read_only,
+        in_recovery,
+        replay_lag,
+    ) = row;
+
+    SqlServerStatus {
+        current_user,
+        privileges,
+        connections: connections.and_then(|count| u64::try_from(count).ok()),
+        max_connections: max_connections.and_then(|max| u64::try_from(max).ok()),
+        database_size_bytes: database_size_bytes.and_then(|size| u64::try_from(size).ok()),
+        read_only,
+        role: in_recovery.map(|in_recovery| {
+            if in_recovery {
+                SqlReplicationRole::Replica
+            } else {
+                SqlReplicationRole::Primary
+            }
+        }),
+        // The lag is the time since the last replayed transaction, so it also grows while the
+        // primary is idle, and a primary may still report the replay of its recovery
+        replica_lag_seconds: replay_lag.filter(|_| in_recovery == Some(true)),
+    }
+}
+
+/// Columns of the tables of the given schemas, with their standard and internal type names
+/// (e.g. `character varying` and `varchar`)
+async fn list_columns(pool: &PgPool, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+    let rows = sqlx::query_as::<_, (String, String, String, String, String)>(
+        "SELECT table_schema, table_name, column_name, data_type, udt_name
+         FROM information_schema.columns
+         WHERE table_schema = ANY($1)
+         ORDER BY table_schema, table_name, ordinal_position",
+    )
+    .bind(schemas)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(schema, table, name, data_type, udt_name)| TableColumn {
+            table: table_name(schema, table, schemas),
+            name,
+            types: vec![data_type, udt_name],
+        })
+        .collect())
+}
+
+/// Write probe statements in the PostgreSQL dialect
+fn probe_statements(table: &str) -> ProbeStatements {
+    let create_table = format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+             token VARCHAR(255) PRIMARY KEY,
+             written_at TIMESTAMPTZ NOT NULL DEFAULT now()
+         )",
+        table
+    );
+
+    ProbeStatements::new(table, create_table, "$1")
+}
+
+/// Decode a column of the validation query into JSON
+/// Types without a JSON equivalent (e.g. NUMERIC) are shown by name, cast them to text to see
+/// their value
+fn column_value(row: &PgRow, index: usize) -> Value {
+    decode_as::<Postgres, bool>(row, index)
+        .or_else(|| decode_as::<Postgres, i16>(row, index))
+        .or_else(|| decode_as::<Postgres, i32>(row, index))
+        .or_else(|| decode_as::<Postgres, i64>(row, index))
+        .or_else(|| decode_as::<Postgres, f32>(row, index))
+        .or_else(|| decode_as::<Postgres, f64>(row, index))
+        .or_else(|| decode_as::<Postgres, String>(row, index))
+        .or_else(|| decode_as::<Postgres, Value>(row, index))
+        .or_else(|| decode_as::<Postgres, DateTime<Utc>>(row, index))
+        .or_else(|| decode_as::<Postgres, NaiveDateTime>(row, index))
+        .or_else(|| decode_as::<Postgres, NaiveDate>(row, index))
+        .or_else(|| decode_as::<Postgres, NaiveTime>(row, index))
+        .or_else(|| {
+            row.try_get::<Option<Uuid>, _>(index)
+                .ok()
+                .map(|uuid| uuid.map(|uuid| uuid.to_string()).into())
+        })
+        .unwrap_or_else(|| unsupported_type(row, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_status_from_row() {
+        let status = server_status_from_row((
+            Some("app".to_string()),
+            Some(vec!["CONNECT".to_string(), "TEMPORARY".to_string()]),
+            Some(87),
+            Some(100),
+            Some(7683431),
+            Some(true),
+            Some(true),
+            Some(0.42),
+        ));
+
+        assert_eq!(status.current_user.as_deref(), Some("app"));
+        assert_eq!(
+            status.privileges,
+            Some(vec!["CONNECT".to_string(), "TEMPORARY".to_string()])
+        );
+        assert_eq!(status.connections, Some(87));
+        assert_eq!(status.max_connections, Some(100));
+        assert_eq!(status.database_size_bytes, Some(7683431));
+        assert_eq!(status.read_only, Some(true));
+        assert!(matches!(status.role, Some(SqlReplicationRole::Replica)));
+        assert_eq!(status.replica_lag_seconds, Some(0.42));
+
+        // A primary has no replica lag, and a size that cannot be read is omitted
+        let status = server_status_from_row((
+            None,
+            None,
+            Some(1),
+            Some(100),
+            None,
+            Some(false),
+            Some(false),
+            Some(3.0),
+        ));
+        assert!(matches!(status.role, Some(SqlReplicationRole::Primary)));
+        assert_eq!(status.replica_lag_seconds, None);
+        assert_eq!(status.database_size_bytes, None);
+        assert_eq!(status.privileges, None);
+    }
+}
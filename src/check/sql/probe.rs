@@ -0,0 +1,204 @@
+use crate::check::duration_ms;
+use crate::models::{SqlProbeStep, SqlWriteProbeResult};
+use chrono::Utc;
+use sqlx::{ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Pool, Type};
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use tracing::error;
+
+/// Table of the write probe when SQL_{id}_PROBE_TABLE is unset
+pub const DEFAULT_PROBE_TABLE: &str = "pmp_health_probe";
+
+/// Statements of the write probe in the dialect of a driver
+pub struct ProbeStatements {
+    pub create_table: String,
+    pub insert: String,
+    pub select: String,
+    pub delete: String,
+}
+
+impl ProbeStatements {
+    /// Statements on `table`, whose token column is compared with the `placeholder` parameter
+    /// (e.g. `$1`)
+    pub fn new(table: &str, create_table: String, placeholder: &str) -> Self {
+        Self {
+            create_table,
+            insert: format!("INSERT INTO {} (token) VALUES ({})", table, placeholder),
+            select: format!("SELECT token FROM {} WHERE token = {}", table, placeholder),
+            delete: format!("DELETE FROM {} WHERE token = {}", table, placeholder),
+        }
+    }
+}
+
+/// Whether a probe table name is a plain identifier, optionally qualified with a schema
+/// It is part of the probe statements, so nothing else is accepted
+pub fn is_valid_table_name(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('.').collect();
+
+    parts.len() <= 2
+        && parts.iter().all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Token of a probe row, unique across instances sharing the database and probes of an instance
+pub fn probe_token() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+    format!(
+        "{}-{}-{}-{}",
+        std::env::var("HOSTNAME").unwrap_or_else(|_| "pmp".to_string()),
+        std::process::id(),
+        Utc::now().timestamp_micros(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Steps of a write probe, which stops at the first failing step
+pub struct WriteProbe {
+    result: SqlWriteProbeResult,
+}
+
+impl WriteProbe {
+    pub fn new(table: &str) -> Self {
+        Self {
+            result: SqlWriteProbeResult {
+                success: false,
+                table: table.to_string(),
+                steps: Vec::new(),
+                error: None,
+            },
+        }
+    }
+
+    /// Run and record a step, `None` if it failed
+    pub async fn step<T, E: Display>(
+        &mut self,
+        name: &str,
+        step: impl Future<Output = Result<T, E>>,
+    ) -> Option<T> {
+        let started = Instant::now();
+        let result = step.await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+
+        if let Some(error) = &error {
+            self.result.error = Some(format!("Write probe failed at {}: {}", name, error));
+        }
+        self.result.steps.push(SqlProbeStep {
+            name: name.to_string(),
+            success: error.is_none(),
+            duration_ms: duration_ms(started.elapsed()),
+            error,
+        });
+
+        result.ok()
+    }
+
+    /// Result of the probe, successful if no step failed
+    pub fn finish(mut self) -> SqlWriteProbeResult {
+        self.result.success = self.result.error.is_none();
+        if let Some(error) = &self.result.error {
+            error!("{}", error);
+        }
+
+        self.result
+    }
+}
+
+/// Create the probe table if absent, then insert a row with a unique token, read it back and
+/// delete it in a transaction, which is committed so that the whole write path is exercised
+pub async fn run_write_probe<DB>(
+    pool: &Pool<DB>,
+    table: &str,
+    statements: &ProbeStatements,
+) -> SqlWriteProbeResult
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    String: for<'r> Decode<'r, DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    let token = probe_token();
+    let mut probe = WriteProbe::new(table);
+
+    // DDL commits implicitly in MySQL, so the table is created outside the transaction
+    if probe
+        .step(
+            "create_table",
+            pool.execute(statements.create_table.as_str()),
+        )
+        .await
+        .is_none()
+    {
+        return probe.finish();
+    }
+
+    let Some(mut tx) = probe.step("begin", pool.begin()).await else {
+        return probe.finish();
+    };
+
+    let inserted = probe
+        .step(
+            "insert",
+            sqlx::query::<DB>(&statements.insert)
+                .bind(token.clone())
+                .execute(&mut *tx),
+        )
+        .await;
+    if inserted.is_none() {
+        return probe.finish();
+    }
+
+    let found = probe
+        .step("select", async {
+            sqlx::query_scalar::<DB, String>(&statements.select)
+                .bind(token.clone())
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "the inserted row was not found".to_string())
+        })
+        .await;
+    if found.is_none() {
+        return probe.finish();
+    }
+
+    let deleted = probe
+        .step(
+            "delete",
+            sqlx::query::<DB>(&statements.delete)
+                .bind(token.clone())
+                .execute(&mut *tx),
+        )
+        .await;
+    if deleted.is_some() {
+        probe.step("commit", tx.commit()).await;
+    }
+
+    probe.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_table_names() {
+        assert!(is_valid_table_name(DEFAULT_PROBE_TABLE));
+        assert!(is_valid_table_name("ops.Health_2"));
+        assert!(!is_valid_table_name("2fast"));
+        assert!(!is_valid_table_name("a.b.c"));
+        assert!(!is_valid_table_name("probe; DROP TABLE users"));
+        assert!(!is_valid_table_name(""));
+    }
+
+    #[test]
+    fn test_probe_tokens_are_unique() {
+        assert_ne!(probe_token(), probe_token());
+    }
+}
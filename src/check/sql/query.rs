@@ -0,0 +1,271 @@
+use crate::models::{SqlConfig, SqlQueryResult};
+use futures::TryStreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{
+    Column, ColumnIndex, Database, Decode, Executor, FromRow, IntoArguments, Pool, Row, Type,
+};
+use tracing::warn;
+
+/// Rows included in the result when SQL_{id}_MAX_ROWS is unset
+pub const DEFAULT_MAX_ROWS: usize = 10;
+
+/// Upper bound of SQL_{id}_MAX_ROWS, to keep results small
+pub const MAX_ROWS_LIMIT: usize = 1000;
+
+/// Decode a column of a row into JSON
+pub type DecodeColumn<DB> = fn(&<DB as Database>::Row, usize) -> Value;
+
+/// Run the validation query of the check in a read-only transaction, which is rolled back,
+/// and compare its result with the expectations
+/// `begin` starts a read-only transaction in the dialect of the driver
+pub async fn run_validation_query<DB>(
+    pool: &Pool<DB>,
+    begin: &str,
+    sql: &str,
+    config: &SqlConfig,
+    decode: DecodeColumn<DB>,
+) -> SqlQueryResult
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+{
+    let mut result = empty_query_result();
+
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => return complete(result, Err(format!("Query failed: {}", e)), config),
+    };
+
+    if let Err(e) = conn.execute(begin).await {
+        let error = format!("Failed to start a read-only transaction: {}", e);
+        return complete(result, Err(error), config);
+    }
+
+    // A prepared statement, so that the query cannot end the transaction with a second statement
+    let fetched = {
+        let mut rows = sqlx::query::<DB>(sql).fetch(&mut *conn);
+        loop {
+            match rows.try_next().await {
+                Ok(Some(row)) => {
+                    if result.columns.is_empty() {
+                        result.columns = row
+                            .columns()
+                            .iter()
+                            .map(|column| column.name().to_string())
+                            .collect();
+                    }
+                    add_row(&mut result, config.max_rows, || {
+                        (0..row.len()).map(|index| decode(&row, index)).collect()
+                    });
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(format!("Query failed: {}", e)),
+            }
+        }
+    };
+
+    // The pool is closed after the check, rolling back is only good manners
+    let _ = conn.execute("ROLLBACK").await;
+
+    complete(result, fetched, config)
+}
+
+/// Fetch a single value (e.g. the server version), `None` with a warning if the query fails,
+/// since the user may lack the privileges it needs
+pub async fn fetch_scalar<DB, T>(pool: &Pool<DB>, sql: &str) -> Option<T>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    (T,): for<'r> FromRow<'r, DB::Row>,
+    T: Send + Unpin,
+{
+    match sqlx::query_scalar::<DB, T>(sql).fetch_one(pool).await {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Failed to run '{}': {}", sql, e);
+            None
+        }
+    }
+}
+
+/// Result of a validation query that has not returned any row yet
+pub fn empty_query_result() -> SqlQueryResult {
+    SqlQueryResult {
+        success: false,
+        columns: Vec::new(),
+        rows: Vec::new(),
+        row_count: 0,
+        truncated: false,
+        error: None,
+    }
+}
+
+/// Count a row, and keep its values while fewer than `max_rows` rows are kept
+pub fn add_row(result: &mut SqlQueryResult, max_rows: usize, values: impl FnOnce() -> Vec<Value>) {
+    if result.rows.len() < max_rows {
+        result.rows.push(values());
+    } else {
+        result.truncated = true;
+    }
+    result.row_count += 1;
+}
+
+/// Complete the result with the outcome of the query, then compare it with the expectations
+pub fn complete(
+    mut result: SqlQueryResult,
+    outcome: Result<(), String>,
+    config: &SqlConfig,
+) -> SqlQueryResult {
+    result.error = match outcome {
+        Ok(()) => unmet_expectation(config, &result),
+        Err(e) => Some(e),
+    };
+    result.success = result.error.is_none();
+
+    result
+}
+
+/// Describe the first expectation that the result does not meet
+fn unmet_expectation(config: &SqlConfig, result: &SqlQueryResult) -> Option<String> {
+    if let Some(expected) = &config.expect_rows
+        && !expected.matches(result.row_count)
+    {
+        return Some(format!(
+            "Unexpected row count: expected {}, got {}",
+            expected, result.row_count
+        ));
+    }
+
+    if config.expect_equals.is_none() && config.expect_contains.is_none() {
+        return None;
+    }
+
+    // Rows are only missing from the result when MAX_ROWS is 0
+    let Some(value) = result.rows.first().and_then(|row| row.first()) else {
+        return Some("Unexpected result: the query returned no value".to_string());
+    };
+    let value = scalar_text(value);
+
+    if let Some(expected) = &config.expect_equals
+        && &value != expected
+    {
+        return Some(format!(
+            "Unexpected value: expected '{}', got '{}'",
+            expected, value
+        ));
+    }
+
+    if let Some(expected) = &config.expect_contains
+        && !value.contains(expected.as_str())
+    {
+        return Some(format!(
+            "Unexpected value: expected it to contain '{}', got '{}'",
+            expected, value
+        ));
+    }
+
+    None
+}
+
+/// Text compared with EXPECT_EQUALS and EXPECT_CONTAINS: strings as is, other values as JSON
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Decode a column as `T` into JSON, `None` if the column is not of type `T`
+pub fn decode_as<'r, DB, T>(row: &'r DB::Row, index: usize) -> Option<Value>
+where
+    DB: Database,
+    T: Decode<'r, DB> + Type<DB> + Serialize,
+    usize: ColumnIndex<DB::Row>,
+{
+    row.try_get::<Option<T>, _>(index)
+        .ok()
+        .map(|value| serde_json::to_value(value).unwrap_or_default())
+}
+
+/// Placeholder of values whose type has no JSON equivalent (e.g. `<NUMERIC>`)
+pub fn unsupported_type<R: Row>(row: &R, index: usize) -> Value {
+    use sqlx::TypeInfo;
+
+    Value::String(format!("<{}>", row.columns()[index].type_info().name()))
+}
+
+/// Name of a table, qualified with its schema when tables of several schemas are listed
+pub fn table_name(schema: String, table: String, schemas: &[String]) -> String {
+    if schemas.len() > 1 {
+        format!("{}.{}", schema, table)
+    } else {
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Check;
+    use crate::check::sql::SqlCheck;
+    use crate::models::RowCountExpectation;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn result(rows: Vec<Vec<Value>>, row_count: u64) -> SqlQueryResult {
+        SqlQueryResult {
+            success: true,
+            columns: vec!["version".to_string()],
+            rows,
+            row_count,
+            truncated: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_unmet_expectation() {
+        let params = HashMap::from([("driver".to_string(), "postgres".to_string())]);
+        let mut config = SqlCheck::parse_config("MAIN", &params).unwrap();
+        config.expect_rows = RowCountExpectation::parse(">= 1");
+        config.expect_equals = Some("42".to_string());
+
+        assert_eq!(
+            unmet_expectation(&config, &result(vec![vec![json!(42)]], 1)),
+            None
+        );
+        assert_eq!(
+            unmet_expectation(&config, &result(vec![], 0)).unwrap(),
+            "Unexpected row count: expected >= 1, got 0"
+        );
+        assert_eq!(
+            unmet_expectation(&config, &result(vec![vec![json!(41)]], 1)).unwrap(),
+            "Unexpected value: expected '42', got '41'"
+        );
+
+        config.expect_equals = None;
+        config.expect_contains = Some("ok".to_string());
+        assert_eq!(
+            unmet_expectation(&config, &result(vec![vec![json!("status: ok")]], 1)),
+            None
+        );
+        assert!(unmet_expectation(&config, &result(vec![vec![Value::Null]], 1)).is_some());
+    }
+
+    #[test]
+    fn test_row_count_expectation() {
+        let expected = RowCountExpectation::parse(">=42").unwrap();
+        assert!(expected.matches(42));
+        assert!(!expected.matches(41));
+        assert_eq!(expected.to_string(), ">= 42");
+
+        assert!(RowCountExpectation::parse("3").unwrap().matches(3));
+        assert!(RowCountExpectation::parse("!= 0").unwrap().matches(1));
+        assert!(RowCountExpectation::parse("< 2").unwrap().matches(1));
+        assert!(RowCountExpectation::parse("many").is_none());
+        assert!(RowCountExpectation::parse(">= -1").is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config_file::load_data_file;
+use crate::models::{SqlConfig, SqlSchemaResult, SqlTypeMismatch};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use tracing::error;
+
+/// Expected columns and types by table, from SQL_{id}_SCHEMA_MANIFEST
+/// Tables are named as in the table listing, a column without type (`~` in YAML, `""` in TOML)
+/// only has to exist
+/// Format:
+/// ```yaml
+/// orders:
+///   id: bigint
+///   total: numeric
+///   note: ~
+/// customers: {}
+/// ```
+pub type SchemaManifest = BTreeMap<String, BTreeMap<String, Option<String>>>;
+
+/// Column of a listed table, with the names of its type (e.g. `character varying` and `varchar`)
+#[derive(Debug, Clone)]
+pub struct TableColumn {
+    pub table: String,
+    pub name: String,
+    pub types: Vec<String>,
+}
+
+/// Load the schema manifest of the check
+pub fn load_manifest(path: &str) -> Result<SchemaManifest, String> {
+    load_data_file(path).map_err(|e| format!("{:#}", e))
+}
+
+/// Compare the listed tables with the expected schema of the check, `None` if neither
+/// SQL_{id}_EXPECT_TABLES nor SQL_{id}_SCHEMA_MANIFEST is set
+/// Columns are only listed, with `list_columns`, when the manifest expects some
+pub async fn check_schema<F, Fut>(
+    config: &SqlConfig,
+    tables: &[String],
+    list_columns: F,
+) -> Option<SqlSchemaResult>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<TableColumn>, String>>,
+{
+    if config.expect_tables.is_empty() && config.schema_manifest.is_none() {
+        return None;
+    }
+
+    let manifest = match config.schema_manifest.as_deref().map(load_manifest) {
+        Some(Ok(manifest)) => manifest,
+        Some(Err(e)) => return Some(failed(format!("Invalid schema manifest: {}", e))),
+        None => SchemaManifest::new(),
+    };
+
+    let columns = if manifest.values().any(|columns| !columns.is_empty()) {
+        match list_columns().await {
+            Ok(columns) => columns,
+            Err(e) => return Some(failed(format!("Failed to retrieve columns: {}", e))),
+        }
+    } else {
+        Vec::new()
+    };
+
+    let result = compare_schema(&config.expect_tables, &manifest, tables, &columns);
+    if let Some(error) = &result.error {
+        error!("{}", error);
+    }
+
+    Some(result)
+}
+
+fn failed(error: String) -> SqlSchemaResult {
+    error!("{}", error);
+    SqlSchemaResult {
+        error: Some(error),
+        ..SqlSchemaResult::default()
+    }
+}
+
+/// Report the expected tables and columns that do not exist, and the columns of another type
+/// Types are compared case-insensitively with every name of the column type
+pub fn compare_schema(
+    expected_tables: &[String],
+    manifest: &SchemaManifest,
+    tables: &[String],
+    columns: &[TableColumn],
+) -> SqlSchemaResult {
+    let mut table_columns: HashMap<(&str, &str), &TableColumn> = HashMap::new();
+    for column in columns {
+        table_columns.insert((&column.table, &column.name), column);
+    }
+
+    let mut result = SqlSchemaResult::default();
+
+    let mut expected: Vec<&String> = expected_tables.iter().chain(manifest.keys()).collect();
+    expected.sort();
+    expected.dedup();
+    result.missing_tables = expected
+        .into_iter()
+        .filter(|table| !tables.contains(table))
+        .cloned()
+        .collect();
+
+    for (table, expected_columns) in manifest {
+        // The columns of missing tables are not reported separately
+        if result.missing_tables.contains(table) {
+            continue;
+        }
+
+        for (name, expected_type) in expected_columns {
+            let column_name = format!("{}.{}", table, name);
+            let Some(column) = table_columns.get(&(table.as_str(), name.as_str())) else {
+                result.missing_columns.push(column_name);
+                continue;
+            };
+
+            if let Some(expected_type) = expected_type.as_deref().map(str::trim)
+                && !expected_type.is_empty()
+                && !column
+                    .types
+                    .iter()
+                    .any(|actual| actual.eq_ignore_ascii_case(expected_type))
+            {
+                result.type_mismatches.push(SqlTypeMismatch {
+                    column: column_name,
+                    expected: expected_type.to_string(),
+                    actual: column.types.first().cloned().unwrap_or_default(),
+                });
+            }
+        }
+    }
+
+    result.error = describe_differences(&result);
+    result.success = result.error.is_none();
+    result
+}
+
+/// Summary of the differences, e.g. `Schema mismatch: missing tables: orders; ...`
+fn describe_differences(result: &SqlSchemaResult) -> Option<String> {
+    let mut differences = Vec::new();
+
+    if !result.missing_tables.is_empty() {
+        differences.push(format!(
+            "missing tables: {}",
+            result.missing_tables.join(", ")
+        ));
+    }
+
+    if !result.missing_columns.is_empty() {
+        differences.push(format!(
+            "missing columns: {}",
+            result.missing_columns.join(", ")
+        ));
+    }
+
+    if !result.type_mismatches.is_empty() {
+        let mismatches: Vec<String> = result
+            .type_mismatches
+            .iter()
+            .map(|m| format!("{} (expected {}, got {})", m.column, m.expected, m.actual))
+            .collect();
+        differences.push(format!("unexpected types: {}", mismatches.join(", ")));
+    }
+
+    (!differences.is_empty()).then(|| format!("Schema mismatch: {}", differences.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(table: &str, name: &str, types: &[&str]) -> TableColumn {
+        TableColumn {
+            table: table.to_string(),
+            name: name.to_string(),
+            types: types.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_compare_schema() {
+        let manifest: SchemaManifest = serde_yaml::from_str(
+            "
+            orders:
+              id: BIGINT
+              total: numeric
+              note: ~
+              shipped_at: timestamp with time zone
+            invoices: {}
+            ",
+        )
+        .unwrap();
+        let tables = vec!["customers".to_string(), "orders".to_string()];
+        let columns = vec![
+            column("orders", "id", &["bigint", "int8"]),
+            column("orders", "total", &["integer", "int4"]),
+            column("orders", "note", &["text", "text"]),
+        ];
+
+        let result = compare_schema(
+            &["customers".to_string(), "audit_log".to_string()],
+            &manifest,
+            &tables,
+            &columns,
+        );
+        assert!(!result.success);
+        assert_eq!(result.missing_tables, vec!["audit_log", "invoices"]);
+        assert_eq!(result.missing_columns, vec!["orders.shipped_at"]);
+        assert_eq!(
+            result.type_mismatches,
+            vec![SqlTypeMismatch {
+                column: "orders.total".to_string(),
+                expected: "numeric".to_string(),
+                actual: "integer".to_string(),
+            }]
+        );
+        assert_eq!(
+            result.error.unwrap(),
+            "Schema mismatch: missing tables: audit_log, invoices; missing columns: orders.shipped_at; unexpected types: orders.total (expected numeric, got integer)"
+        );
+
+        let result = compare_schema(&tables, &SchemaManifest::new(), &tables, &[]);
+        assert!(result.success);
+        assert_eq!(result.error, None);
+    }
+
+    #[test]
+    fn test_toml_manifest_without_types() {
+        let manifest: SchemaManifest = toml::from_str(
+            r#"
+            [orders]
+            id = "bigint"
+            note = ""
+            "#,
+        )
+        .unwrap();
+        let columns = vec![
+            column("orders", "id", &["bigint"]),
+            column("orders", "note", &["text"]),
+        ];
+
+        let result = compare_schema(&[], &manifest, &["orders".to_string()], &columns);
+        assert!(result.success, "{:?}", result.error);
+    }
+}
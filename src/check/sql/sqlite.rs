@@ -0,0 +1,337 @@
+use super::check_database;
+use super::driver::{SqlDriver, SqlSession};
+use super::probe::{ProbeStatements, WriteProbe, run_write_probe};
+use super::query::{decode_as, fetch_scalar, run_validation_query, table_name, unsupported_type};
+use super::schema::TableColumn;
+use crate::check::connection::query_string;
+use crate::models::{SqlConfig, SqlQueryResult, SqlWriteProbeResult};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{Sqlite, SqlitePool};
+use std::str::FromStr;
+
+/// Characters escaped in file names, which sqlx percent-decodes
+const FILE_NAME: &AsciiSet = &CONTROLS.add(b'%').add(b'?').add(b'#');
+
+/// SQLite driver, the database is the path of a file (e.g. a database shared with a sidecar)
+pub static SQLITE: SqlDriver = SqlDriver {
+    name: "sqlite",
+    aliases: &["sqlite3"],
+    scheme: "sqlite",
+    default_port: 0,
+    default_user: "",
+    default_database: ":memory:",
+    file_url: true,
+    client_certificate: false,
+    check_query: None,
+    check: |config| Box::pin(check_database::<SqlitePool>(config)),
+};
+
+impl SqlSession for SqlitePool {
+    const LABEL: &'static str = "SQLite";
+
+    async fn connect(config: &SqlConfig) -> Result<Self, String> {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(connect_options(config).map_err(|e| e.to_string())?)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Attached databases are schemas, the database file is `main`
+    fn default_schemas(_config: &SqlConfig) -> Vec<String> {
+        vec!["main".to_string()]
+    }
+
+    async fn version(&mut self) -> Option<String> {
+        fetch_scalar(self, "SELECT sqlite_version()").await
+    }
+
+    async fn list_tables(&mut self, schemas: &[String]) -> Result<Vec<(String, String)>, String> {
+        list_tables(self, schemas).await.map_err(|e| e.to_string())
+    }
+
+    async fn list_columns(&mut self, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+        list_columns(self, schemas).await
+    }
+
+    /// The database is opened read-only, so a plain transaction is enough
+    async fn run_query(&mut self, sql: &str, config: &SqlConfig) -> SqlQueryResult {
+        run_validation_query(self, "BEGIN", sql, config, column_value).await
+    }
+
+    async fn run_write_probe(&mut self, config: &SqlConfig) -> SqlWriteProbeResult {
+        run_probe(config, &config.probe_table).await
+    }
+}
+
+/// Options of the database file, opened read-only so that neither the check nor the validation
+/// query can create or modify it
+/// The options of the check are passed as URL parameters (e.g. `{"immutable":"true"}`)
+fn connect_options(config: &SqlConfig) -> Result<SqliteConnectOptions, sqlx::Error> {
+    let url = format!(
+        "sqlite:{}{}",
+        utf8_percent_encode(&config.database, FILE_NAME),
+        query_string(&config.options)
+    );
+
+    let options = SqliteConnectOptions::from_str(&url)?.create_if_missing(false);
+
+    // An in-memory database only exists for the check, there is nothing to protect
+    if config.database == ":memory:" {
+        Ok(options)
+    } else {
+        Ok(options.read_only(true))
+    }
+}
+
+/// Tables and views of the given schemas, excluding the internal tables of SQLite and the shadow
+/// tables of virtual tables
+async fn list_tables(
+    pool: &SqlitePool,
+    schemas: &[String],
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    let placeholders = vec!["?"; schemas.len()].join(", ");
+    let sql = format!(
+        "SELECT schema, name
+         FROM pragma_table_list()
+         WHERE type != 'shadow' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' AND schema IN ({})
+         ORDER BY schema, name",
+        placeholders
+    );
+
+    schemas
+        .iter()
+        .fold(
+            sqlx::query_as::<_, (String, String)>(&sql),
+            |query, schema| query.bind(schema),
+        )
+        .fetch_all(pool)
+        .await
+}
+
+/// Columns of the tables of the given schemas, with their declared type (e.g. `INTEGER`)
+async fn list_columns(pool: &SqlitePool, schemas: &[String]) -> Result<Vec<TableColumn>, String> {
+    let placeholders = vec!["?"; schemas.len()].join(", ");
+    let sql = format!(
+        "SELECT t.schema, t.name, c.name, c.type
+         FROM pragma_table_list() AS t
+         JOIN pragma_table_info(t.name, t.schema) AS c
+         WHERE t.type != 'shadow' AND t.schema IN ({})
+         ORDER BY t.schema, t.name, c.cid",
+        placeholders
+    );
+
+    let rows = schemas
+        .iter()
+        .fold(
+            sqlx::query_as::<_, (String, String, String, String)>(&sql),
+            |query, schema| query.bind(schema),
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(schema, table, name, declared_type)| TableColumn {
+            table: table_name(schema, table, schemas),
+            name,
+            types: vec![declared_type],
+        })
+        .collect())
+}
+
+/// Write probe statements in the SQLite dialect
+fn probe_statements(table: &str) -> ProbeStatements {
+    let create_table = format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+             token TEXT PRIMARY KEY,
+             written_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+         )",
+        table
+    );
+
+    ProbeStatements::new(table, create_table, "?")
+}
+
+/// Run the write probe on a separate read-write connection, the check connection is read-only
+async fn run_probe(config: &SqlConfig, table: &str) -> SqlWriteProbeResult {
+    let pool = async {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(connect_options(config)?.read_only(false))
+            .await
+    }
+    .await;
+
+    match pool {
+        Ok(pool) => run_write_probe(&pool, table, &probe_statements(table)).await,
+        Err(e) => {
+            let mut probe = WriteProbe::new(table);
+            probe.step("connect", async { Err::<(), _>(e) }).await;
+            probe.finish()
+        }
+    }
+}
+
+/// Decode a column of the validation query into JSON, by storage class since SQLite columns
+/// accept values of any type
+/// Blobs are shown by type name
+fn column_value(row: &SqliteRow, index: usize) -> Value {
+    decode_as::<Sqlite, i64>(row, index)
+        .or_else(|| decode_as::<Sqlite, f64>(row, index))
+        .or_else(|| decode_as::<Sqlite, String>(row, index))
+        .unwrap_or_else(|| unsupported_type(row, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Check;
+    use crate::check::sql::SqlCheck;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn config(params: &[(&str, &str)]) -> SqlConfig {
+        let params: HashMap<String, String> = params
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        SqlCheck::parse_config("LOCAL", &params).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_check_sqlite_file() {
+        let path = std::env::temp_dir().join(format!("sqlite-check-{}.db", std::process::id()));
+        let database = path.to_str().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let pool = SqlitePoolOptions::new()
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(&path)
+                    .create_if_missing(true),
+            )
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL, note TEXT, payload BLOB);
+             CREATE TABLE customers (id INTEGER PRIMARY KEY);
+             CREATE VIEW recent_orders AS SELECT id FROM orders;
+             INSERT INTO orders VALUES (1, 9.5, 'first', x'00'), (2, 3.0, NULL, NULL);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let result = check_database::<SqlitePool>(config(&[
+            ("url", &format!("sqlite://{}", database)),
+            ("query", "SELECT * FROM orders ORDER BY id"),
+            ("expect_rows", "2"),
+        ]))
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.driver, "sqlite");
+        assert!(result.version.is_some());
+        assert_eq!(
+            result.tables,
+            Some(vec![
+                "customers".to_string(),
+                "orders".to_string(),
+                "recent_orders".to_string()
+            ])
+        );
+        assert_eq!(
+            result.query.unwrap().rows,
+            vec![
+                vec![json!(1), json!(9.5), json!("first"), json!("<BLOB>")],
+                vec![json!(2), json!(3.0), Value::Null, Value::Null],
+            ]
+        );
+
+        // The database is opened read-only
+        let result = check_database::<SqlitePool>(config(&[
+            ("driver", "sqlite"),
+            ("database", database),
+            ("query", "DELETE FROM orders RETURNING id"),
+        ]))
+        .await;
+        assert!(!result.success);
+        assert!(result.query.unwrap().error.unwrap().contains("readonly"));
+
+        // The tables are compared with the expected schema
+        let manifest = path.with_extension("yaml");
+        std::fs::write(
+            &manifest,
+            "orders:\n  id: integer\n  total: numeric\n  shipped_at: ~\n",
+        )
+        .unwrap();
+        let result = check_database::<SqlitePool>(config(&[
+            ("driver", "sqlite"),
+            ("database", database),
+            ("expect_tables", "customers, invoices"),
+            ("schema_manifest", manifest.to_str().unwrap()),
+        ]))
+        .await;
+        let schema = result.schema.unwrap();
+        assert_eq!(schema.missing_tables, vec!["invoices"]);
+        assert_eq!(schema.missing_columns, vec!["orders.shipped_at"]);
+        assert_eq!(schema.type_mismatches[0].actual, "REAL");
+        assert_eq!(result.error, schema.error);
+        assert!(!result.success);
+
+        let _ = std::fs::remove_file(&manifest);
+
+        // The write probe writes on a separate connection, and leaves no row behind
+        let result = check_database::<SqlitePool>(config(&[
+            ("driver", "sqlite"),
+            ("database", database),
+            ("write_probe", "true"),
+        ]))
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        let probe = result.write_probe.unwrap();
+        let steps: Vec<&str> = probe.steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            steps,
+            vec![
+                "create_table",
+                "begin",
+                "insert",
+                "select",
+                "delete",
+                "commit"
+            ]
+        );
+
+        let result = check_database::<SqlitePool>(config(&[
+            ("driver", "sqlite"),
+            ("database", database),
+            ("query", "SELECT count(*) FROM pmp_health_probe"),
+            ("expect_equals", "0"),
+        ]))
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.write_probe.is_none());
+
+        let _ = std::fs::remove_file(&path);
+
+        // Missing files are not created
+        let result =
+            check_database::<SqlitePool>(config(&[("driver", "sqlite"), ("database", database)]))
+                .await;
+        assert!(result.error.unwrap().starts_with("Connection failed"));
+        assert!(!path.exists());
+
+        // A relative path in the URL is the file, not the host of an in-memory database
+        let result =
+            check_database::<SqlitePool>(config(&[("url", "sqlite://pmp-missing-check.db")])).await;
+        assert_eq!(result.database, "pmp-missing-check.db");
+        assert!(result.error.unwrap().starts_with("Connection failed"));
+        assert!(!std::path::Path::new("pmp-missing-check.db").exists());
+    }
+}
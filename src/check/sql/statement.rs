@@ -0,0 +1,161 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Lexical rules of a SQL dialect, to split validation queries into words
+pub struct Lexicon {
+    /// Characters opening a quoted string or identifier, with the character closing it
+    /// A doubled closing character is escaped (e.g. 'it''s')
+    pub quotes: &'static [(char, char)],
+
+    /// Whether a backslash escapes the next character of `'` and `"` strings
+    pub backslash_escapes: bool,
+
+    /// Whether `--` only starts a comment when followed by a space (e.g. `1--1` is `1 - -1`)
+    pub spaced_dash_comments: bool,
+
+    /// Whether block comments nest
+    pub nested_comments: bool,
+
+    /// Whether `#` starts a comment, otherwise it is part of words (e.g. `#temp`)
+    pub hash_comments: bool,
+
+    /// Whether the content of `/*! ... */` comments is executed, which rejects them
+    pub executable_comments: bool,
+}
+
+/// Upper-cased words of a SQL text, without comments, literals and quoted identifiers
+/// Only comments may follow a statement separator, since a single statement is accepted
+pub fn query_words(sql: &str, lexicon: &Lexicon) -> Result<Vec<String>, String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = sql.chars().peekable();
+    let mut ended = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') && dash_comment(&chars, lexicon) => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '#' if lexicon.hash_comments => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if lexicon.executable_comments && chars.peek() == Some(&'!') {
+                    return Err("Executable comments (/*! ... */) are not accepted".to_string());
+                }
+                skip_comment(&mut chars, lexicon)?;
+            }
+            ';' => ended = true,
+            _ if ended => return Err("Only a single statement is accepted".to_string()),
+            c if lexicon.quotes.iter().any(|&(open, _)| open == c) => {
+                skip_quoted(&mut chars, c, lexicon)?;
+            }
+            c if is_word_char(c, lexicon) => {
+                let mut word = c.to_uppercase().to_string();
+                while let Some(c) = chars.next_if(|&c| is_word_char(c, lexicon)) {
+                    word.extend(c.to_uppercase());
+                }
+                words.push(word);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(words)
+}
+
+/// Whether the `--` starting at the next character opens a comment
+fn dash_comment(chars: &Peekable<Chars>, lexicon: &Lexicon) -> bool {
+    !lexicon.spaced_dash_comments
+        || chars
+            .clone()
+            .nth(1)
+            .is_none_or(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Skip a block comment, whose opening `/*` was read
+fn skip_comment(chars: &mut Peekable<Chars>, lexicon: &Lexicon) -> Result<(), String> {
+    let mut depth = 1;
+    while depth > 0 {
+        match chars.next() {
+            Some('/') if lexicon.nested_comments && chars.peek() == Some(&'*') => {
+                chars.next();
+                depth += 1;
+            }
+            Some('*') if chars.peek() == Some(&'/') => {
+                chars.next();
+                depth -= 1;
+            }
+            Some(_) => {}
+            None => return Err("Unterminated comment".to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Skip a quoted string or identifier, whose opening character was read
+fn skip_quoted(chars: &mut Peekable<Chars>, open: char, lexicon: &Lexicon) -> Result<(), String> {
+    let close = lexicon
+        .quotes
+        .iter()
+        .find(|&&(quote, _)| quote == open)
+        .map_or(open, |&(_, close)| close);
+    let backslash_escapes = lexicon.backslash_escapes && matches!(open, '\'' | '"');
+
+    loop {
+        match chars.next() {
+            Some('\\') if backslash_escapes => {
+                chars.next();
+            }
+            Some(next) if next == close => {
+                if chars.next_if_eq(&close).is_none() {
+                    return Ok(());
+                }
+            }
+            Some(_) => {}
+            None => return Err(format!("Unterminated {} in the query", open)),
+        }
+    }
+}
+
+/// Whether a character is part of a word (e.g. `@variable` or `#temp`)
+fn is_word_char(c: char, lexicon: &Lexicon) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '$') || (c == '#' && !lexicon.hash_comments)
+}
+
+/// Reject the words of a query that does not start with one of `statements`, or that contains
+/// one of `write_keywords`
+/// `database` names the database in the errors
+pub fn check_words(
+    words: &[String],
+    database: &str,
+    statements: &[&str],
+    write_keywords: &[&str],
+) -> Result<(), String> {
+    match words.first() {
+        Some(word) if statements.contains(&word.as_str()) => {}
+        Some(word) => {
+            return Err(format!(
+                "Only {} queries are accepted by {}, found {}",
+                statements.join("/"),
+                database,
+                word
+            ));
+        }
+        None => return Err("The query is empty".to_string()),
+    }
+
+    if let Some(word) = words
+        .iter()
+        .find(|word| write_keywords.contains(&word.as_str()))
+    {
+        return Err(format!(
+            "Only read-only queries are accepted by {}, found {}",
+            database, word
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,345 @@
+use crate::check::CheckParams;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Checks defined in the file referenced by CONFIG_FILE
+/// Format:
+/// ```yaml
+/// defaults:
+///   interval: 30s
+/// checks:
+///   sql:
+///     main_db:
+///       driver: postgres
+///       password: ${DB_PASSWORD}
+/// ```
+#[derive(Debug, Default)]
+pub struct ConfigFile {
+    /// Settings applied to every check (interval, timeout)
+    pub defaults: HashMap<String, String>,
+
+    /// Check parameters by kind, then identifier
+    pub checks: HashMap<String, CheckParams>,
+}
+
+/// File layout before interpolation, values may be nested (e.g. HTTP headers)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfigFile {
+    #[serde(default)]
+    defaults: HashMap<String, Value>,
+
+    #[serde(default)]
+    checks: HashMap<String, HashMap<String, HashMap<String, Value>>>,
+}
+
+/// Config file format, detected from the file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigFormat {
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    fn from_path(path: &str) -> Result<Self> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            Some("toml") => Ok(Self::Toml),
+            _ => bail!("Unsupported file {}, expected .yaml, .yml or .toml", path),
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_str(content)?,
+            Self::Toml => toml::from_str(content)?,
+        })
+    }
+}
+
+/// Load a YAML (.yaml, .yml) or TOML (.toml) config file
+/// `${VAR}` and `${VAR:-default}` in values are replaced with the given variables
+pub fn load_config_file(path: &str, vars: &[(String, String)]) -> Result<ConfigFile> {
+    let format = ConfigFormat::from_path(path)?;
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read config file {}", path))?;
+
+    parse_config_file(&content, format, vars)
+        .with_context(|| format!("Invalid config file {}", path))
+}
+
+/// Load a YAML (.yaml, .yml) or TOML (.toml) file referenced by a check (e.g. a schema
+/// manifest), without interpolation
+pub fn load_data_file<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let format = ConfigFormat::from_path(path)?;
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+
+    format
+        .parse(&content)
+        .with_context(|| format!("Invalid file {}", path))
+}
+
+/// Load the variables of every env-style (KEY=VALUE) file in a directory, e.g. a mounted ConfigMap
+/// Files are read in name order, later files override earlier ones. Hidden entries are skipped,
+/// which also skips the `..data` links Kubernetes uses to swap mounted files atomically.
+pub fn load_env_dir(path: &str) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+
+    for entry in
+        fs::read_dir(path).with_context(|| format!("Failed to read config directory {}", path))?
+    {
+        let entry = entry.with_context(|| format!("Failed to read config directory {}", path))?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        // Follow symlinks, mounted ConfigMap keys are links to the current data directory
+        if !hidden && fs::metadata(entry.path()).is_ok_and(|metadata| metadata.is_file()) {
+            files.push(entry.path());
+        }
+    }
+
+    files.sort();
+
+    let mut vars = Vec::new();
+    for file in files {
+        let iter = dotenvy::from_path_iter(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+
+        for item in iter {
+            vars.push(item.with_context(|| format!("Invalid env file {}", file.display()))?);
+        }
+    }
+
+    Ok(vars)
+}
+
+fn parse_config_file(
+    content: &str,
+    format: ConfigFormat,
+    vars: &[(String, String)],
+) -> Result<ConfigFile> {
+    let raw: RawConfigFile = format.parse(content)?;
+
+    let mut config = ConfigFile::default();
+
+    for (name, value) in raw.defaults {
+        if let Some(value) =
+            param_value(value, vars).with_context(|| format!("defaults.{}", name))?
+        {
+            config.defaults.insert(name.to_lowercase(), value);
+        }
+    }
+
+    for (kind, checks) in raw.checks {
+        let kind_checks = config.checks.entry(kind.clone()).or_default();
+
+        for (identifier, params) in checks {
+            let check_params = kind_checks.entry(identifier.clone()).or_default();
+
+            for (name, value) in params {
+                let value = param_value(value, vars)
+                    .with_context(|| format!("checks.{}.{}.{}", kind, identifier, name))?;
+
+                if let Some(value) = value {
+                    check_params.insert(name.to_lowercase(), value);
+                }
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// Convert a value to the string form of the matching environment variable
+/// Nested values (e.g. HTTP headers) are encoded as JSON, null values are skipped
+fn param_value(value: Value, vars: &[(String, String)]) -> Result<Option<String>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(value) => interpolate(&value, vars).map(Some),
+        Value::Bool(value) => Ok(Some(value.to_string())),
+        Value::Number(value) => Ok(Some(value.to_string())),
+        nested => Ok(Some(interpolate_nested(nested, vars)?.to_string())),
+    }
+}
+
+fn interpolate_nested(value: Value, vars: &[(String, String)]) -> Result<Value> {
+    Ok(match value {
+        Value::String(value) => Value::String(interpolate(&value, vars)?),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| interpolate_nested(value, vars))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(values) => Value::Object(
+            values
+                .into_iter()
+                .map(|(key, value)| Ok((key, interpolate_nested(value, vars)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => value,
+    })
+}
+
+/// Replace `${VAR}` and `${VAR:-default}` with the value of the variable, `$$` with `$`
+fn interpolate(value: &str, vars: &[(String, String)]) -> Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("Unterminated variable reference in '{}'", value))?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+
+            let variable = vars
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str());
+
+            // Like in shells, the default also replaces empty values
+            match (variable, default) {
+                (Some(""), Some(default)) | (None, Some(default)) => result.push_str(default),
+                (Some(variable), _) => result.push_str(variable),
+                (None, None) => bail!("Environment variable {} is not set", name),
+            }
+
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let vars = vars(&[("DB_HOST", "db.internal"), ("EMPTY", "")]);
+
+        assert_eq!(
+            interpolate("${DB_HOST}:5432", &vars).unwrap(),
+            "db.internal:5432"
+        );
+        assert_eq!(
+            interpolate("${EMPTY:-fallback}", &vars).unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate("${EMPTY}", &vars).unwrap(), "");
+        assert_eq!(interpolate("${MISSING:-}", &vars).unwrap(), "");
+        assert_eq!(
+            interpolate("cost $5 $${HOME}", &vars).unwrap(),
+            "cost $5 ${HOME}"
+        );
+        assert!(interpolate("${MISSING}", &vars).is_err());
+        assert!(interpolate("${DB_HOST", &vars).is_err());
+    }
+
+    #[test]
+    fn test_parse_yaml_config_file() {
+        let content = r#"
+defaults:
+  interval: 30s
+checks:
+  sql:
+    main_db:
+      driver: postgres
+      port: 5433
+      password: ${DB_PASSWORD}
+  http:
+    api:
+      url: https://api.example.com
+      headers:
+        Authorization: Bearer ${TOKEN}
+"#;
+        let config = parse_config_file(
+            content,
+            ConfigFormat::Yaml,
+            &vars(&[("DB_PASSWORD", "secret"), ("TOKEN", "abc")]),
+        )
+        .unwrap();
+
+        assert_eq!(config.defaults["interval"], "30s");
+
+        let main_db = &config.checks["sql"]["main_db"];
+        assert_eq!(main_db["port"], "5433");
+        assert_eq!(main_db["password"], "secret");
+
+        let headers: HashMap<String, String> =
+            serde_json::from_str(&config.checks["http"]["api"]["headers"]).unwrap();
+        assert_eq!(headers["Authorization"], "Bearer abc");
+    }
+
+    #[test]
+    fn test_parse_toml_config_file() {
+        let content = r#"
+[checks.nosql.session_cache]
+driver = "redis"
+host = "${REDIS_HOST:-localhost}"
+critical = false
+"#;
+        let config = parse_config_file(content, ConfigFormat::Toml, &[]).unwrap();
+
+        let cache = &config.checks["nosql"]["session_cache"];
+        assert_eq!(cache["host"], "localhost");
+        assert_eq!(cache["critical"], "false");
+    }
+
+    #[test]
+    fn test_unknown_sections_are_rejected() {
+        assert!(parse_config_file("check: {}", ConfigFormat::Yaml, &[]).is_err());
+    }
+
+    #[test]
+    fn test_load_env_dir() {
+        let dir = std::env::temp_dir().join(format!("pmp-env-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("a-sql"),
+            "SQL_MAIN_DRIVER=postgres\nSQL_MAIN_PORT=5432\n",
+        )
+        .unwrap();
+        fs::write(dir.join("b-override"), "# comment\nSQL_MAIN_PORT=5433\n").unwrap();
+        fs::write(dir.join(".hidden"), "SQL_HIDDEN_DRIVER=mysql\n").unwrap();
+
+        let vars = load_env_dir(dir.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            vars.unwrap(),
+            vec![
+                ("SQL_MAIN_DRIVER".to_string(), "postgres".to_string()),
+                ("SQL_MAIN_PORT".to_string(), "5432".to_string()),
+                ("SQL_MAIN_PORT".to_string(), "5433".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use crate::models::{ErrorResponse, ReloadResponse};
+use crate::reload::ActiveChecks;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use tracing::{info, instrument};
+
+/// Reload endpoint handler
+/// Parses the check configuration again and swaps the active checks
+/// Returns the added, removed and changed checks, or 422 when the configuration is invalid
+#[instrument(skip_all)]
+pub async fn reload_handler(
+    State(checks): State<ActiveChecks>,
+) -> Result<Json<ReloadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!(event = "reload_requested", "Reloading checks");
+
+    checks.reload().await.map(Json).map_err(|error| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse { error }),
+        )
+    })
+}
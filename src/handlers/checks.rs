@@ -0,0 +1,192 @@
+use crate::check::{CheckOutput, find_kind, run_checks};
+use crate::env_parser::parse_info_deadline;
+use crate::models::ErrorResponse;
+use crate::reload::ActiveChecks;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use std::collections::{BTreeMap, HashMap};
+use tokio::time::Instant;
+use tracing::{info, instrument};
+
+type ErrorReply = (StatusCode, Json<ErrorResponse>);
+
+fn not_found(message: String) -> ErrorReply {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: message }),
+    )
+}
+
+/// List configured checks endpoint handler
+/// Returns the identifiers of every configured check, grouped by kind
+pub async fn list_checks_handler(
+    State(checks): State<ActiveChecks>,
+) -> Json<BTreeMap<String, Vec<String>>> {
+    let mut configured: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for check in checks.current().iter() {
+        configured
+            .entry(check.kind.to_string())
+            .or_default()
+            .push(check.identifier.clone());
+    }
+
+    for identifiers in configured.values_mut() {
+        identifiers.sort();
+    }
+
+    Json(configured)
+}
+
+/// Kind checks endpoint handler
+/// Runs every configured check of a single kind
+#[instrument(skip_all, fields(kind = %kind))]
+pub async fn kind_checks_handler(
+    State(checks): State<ActiveChecks>,
+    Path(kind): Path<String>,
+) -> Result<Json<HashMap<String, CheckOutput>>, ErrorReply> {
+    let kind =
+        find_kind(&kind).ok_or_else(|| not_found(format!("Unknown check kind: {}", kind)))?;
+
+    let checks: Vec<_> = checks
+        .current()
+        .iter()
+        .filter(|check| check.kind == kind.kind())
+        .cloned()
+        .collect();
+
+    info!(
+        event = "kind_checks_started",
+        total_checks = checks.len(),
+        "Running checks of a single kind"
+    );
+
+    let deadline = Instant::now() + parse_info_deadline();
+    let mut results = run_checks(checks, Some(deadline)).await;
+
+    Ok(Json(results.remove(kind.kind()).unwrap_or_default()))
+}
+
+/// Single check endpoint handler
+/// Runs one configured check identified by its kind and identifier
+#[instrument(skip_all, fields(kind = %kind, identifier = %identifier))]
+pub async fn single_check_handler(
+    State(checks): State<ActiveChecks>,
+    Path((kind, identifier)): Path<(String, String)>,
+) -> Result<Json<CheckOutput>, ErrorReply> {
+    let kind =
+        find_kind(&kind).ok_or_else(|| not_found(format!("Unknown check kind: {}", kind)))?;
+
+    let check = checks
+        .current()
+        .iter()
+        .find(|check| check.kind == kind.kind() && check.identifier == identifier)
+        .cloned()
+        .ok_or_else(|| {
+            not_found(format!(
+                "No {} check configured with identifier: {}",
+                kind.kind(),
+                identifier
+            ))
+        })?;
+
+    info!(event = "single_check_started", "Running single check");
+
+    Ok(Json(check.run().await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::load_checks_from;
+    use crate::scheduler::{ResultCache, Scheduler};
+    use crate::shutdown::Shutdown;
+
+    /// Active checks of the given variables, an in-memory SQLite database needs no server
+    fn active_checks(pairs: &[(&str, &str)]) -> ActiveChecks {
+        let vars = pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let scheduler = Scheduler::new(ResultCache::default(), Shutdown::default());
+
+        ActiveChecks::new(load_checks_from(vars).checks, scheduler)
+    }
+
+    fn path(kind: &str, identifier: &str) -> Path<(String, String)> {
+        Path((kind.to_string(), identifier.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_single_check_handler() {
+        let checks = active_checks(&[("SQL_MEMORY_DRIVER", "sqlite")]);
+
+        let Ok(Json(output)) =
+            single_check_handler(State(checks.clone()), path("sql", "MEMORY")).await
+        else {
+            panic!("expected the result of the check");
+        };
+        let payload = serde_json::to_value(&output).unwrap();
+        assert!(output.success);
+        assert_eq!(payload["success"], true);
+        assert_eq!(payload["driver"], "sqlite");
+        assert_eq!(payload["database"], ":memory:");
+        assert!(payload["version"].is_string());
+        assert!(payload["checked_at"].is_string());
+        assert!(payload["duration_ms"].is_number());
+        assert!(payload.get("age").is_none());
+
+        for (kind, identifier, message) in [
+            ("ftp", "MEMORY", "Unknown check kind: ftp"),
+            (
+                "sql",
+                "OTHER",
+                "No sql check configured with identifier: OTHER",
+            ),
+            // Identifiers are matched as configured
+            (
+                "sql",
+                "memory",
+                "No sql check configured with identifier: memory",
+            ),
+        ] {
+            let Err((status, Json(error))) =
+                single_check_handler(State(checks.clone()), path(kind, identifier)).await
+            else {
+                panic!("expected {}/{} to be unknown", kind, identifier);
+            };
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(error.error, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kind_checks_handler() {
+        let checks = active_checks(&[
+            ("SQL_MEMORY_DRIVER", "sqlite"),
+            ("SQL_OTHER_DRIVER", "sqlite3"),
+            ("HTTP_API_URL", "http://127.0.0.1:9/"),
+        ]);
+
+        let Ok(Json(results)) =
+            kind_checks_handler(State(checks.clone()), Path("sql".to_string())).await
+        else {
+            panic!("expected the results of the checks");
+        };
+        let mut identifiers: Vec<_> = results.keys().cloned().collect();
+        identifiers.sort();
+        assert_eq!(identifiers, ["MEMORY", "OTHER"]);
+        assert!(results.values().all(|output| output.success));
+
+        let Err((status, Json(error))) =
+            kind_checks_handler(State(checks), Path("ftp".to_string())).await
+        else {
+            panic!("expected ftp to be unknown");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error.error, "Unknown check kind: ftp");
+    }
+}
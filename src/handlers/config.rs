@@ -0,0 +1,56 @@
+use crate::env_parser::parse_strict_config;
+use crate::models::{CheckConfigResponse, ValidationResponse};
+use crate::reload::ActiveChecks;
+use crate::validation::validate_config;
+use axum::Json;
+use axum::extract::State;
+use std::collections::BTreeMap;
+use tracing::{info, instrument};
+
+/// Config endpoint handler
+/// Returns the effective configuration of every active check, grouped by kind, then identifier
+/// Secrets are masked and fields that use their default value are listed in `defaults`
+#[instrument(skip_all)]
+pub async fn config_handler(
+    State(checks): State<ActiveChecks>,
+) -> Json<BTreeMap<String, BTreeMap<String, CheckConfigResponse>>> {
+    let mut configs: BTreeMap<String, BTreeMap<String, CheckConfigResponse>> = BTreeMap::new();
+
+    for check in checks.current().iter() {
+        configs.entry(check.kind.to_string()).or_default().insert(
+            check.identifier.clone(),
+            CheckConfigResponse {
+                config: check.config.clone(),
+                interval: check.interval.map(|interval| format!("{:?}", interval)),
+                timeout: format!("{:?}", check.timeout),
+                critical: check.critical,
+                defaults: check.defaults.clone(),
+            },
+        );
+    }
+
+    Json(configs)
+}
+
+/// Config validation endpoint handler
+/// Validates the current configuration (environment, CONFIG_DIR and CONFIG_FILE) without
+/// running any check, and returns the issues found
+#[instrument(skip_all)]
+pub async fn validate_config_handler() -> Json<ValidationResponse> {
+    let issues = validate_config();
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    let warnings = issues.len() - errors;
+
+    info!(
+        event = "config_validated",
+        errors, warnings, "Validated configuration"
+    );
+
+    Json(ValidationResponse {
+        valid: errors == 0,
+        strict: parse_strict_config(),
+        errors,
+        warnings,
+        issues,
+    })
+}
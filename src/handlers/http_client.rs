@@ -1,8 +1,12 @@
 use crate::models::{HttpClientRequest, HttpClientResponse};
+use crate::telemetry::inject_trace_context;
 use axum::Json;
+use reqwest::header::HeaderMap;
 use std::collections::HashMap;
+use tracing::instrument;
 
 /// API endpoint to execute HTTP requests from the UI
+#[instrument(skip_all, fields(method = %request.method, url = %request.url))]
 pub async fn execute_http_request(
     Json(request): Json<HttpClientRequest>,
 ) -> Json<HttpClientResponse> {
@@ -29,6 +33,11 @@ pub async fn execute_http_request(
         req_builder = req_builder.header(key, value);
     }
 
+    // Add the trace context so the target service joins our trace
+    let mut trace_headers = HeaderMap::new();
+    inject_trace_context(&mut trace_headers);
+    req_builder = req_builder.headers(trace_headers);
+
     // Add body if present
     if let Some(body) = &request.body
         && !body.is_empty()
@@ -1,9 +1,17 @@
+pub mod admin;
+pub mod checks;
+pub mod config;
 pub mod health;
 pub mod http_client;
 pub mod info;
+pub mod ready;
 pub mod ui;
 
+pub use admin::reload_handler;
+pub use checks::{kind_checks_handler, list_checks_handler, single_check_handler};
+pub use config::{config_handler, validate_config_handler};
 pub use health::health_handler;
 pub use http_client::execute_http_request;
 pub use info::info_handler;
+pub use ready::ready_handler;
 pub use ui::ui_handler;
@@ -0,0 +1,115 @@
+use crate::env_parser::parse_info_deadline;
+use crate::models::{FailedCheck, InfoQuery, ReadinessResponse};
+use crate::reload::ActiveChecks;
+use crate::scheduler::{ResultCache, latest_results};
+use crate::shutdown::Shutdown;
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use std::collections::HashMap;
+use tokio::time::Instant;
+use tracing::{info, instrument, warn};
+
+/// Readiness endpoint handler
+/// Returns 200 OK when every critical check succeeds, 503 Service Unavailable otherwise
+/// Always returns 503 once the service is shutting down
+#[instrument(skip_all, fields(fresh = query.fresh))]
+pub async fn ready_handler(
+    State(checks): State<ActiveChecks>,
+    State(cache): State<ResultCache>,
+    State(shutdown): State<Shutdown>,
+    Query(query): Query<InfoQuery>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    if shutdown.is_triggered() {
+        info!(event = "readiness_checked", "Service is shutting down");
+
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ReadinessResponse {
+                ready: false,
+                total_checks: 0,
+                failed_critical: Vec::new(),
+                failed_optional: Vec::new(),
+                shutting_down: true,
+            }),
+        );
+    }
+
+    let checks = checks.current().to_vec();
+    let critical: HashMap<(&str, String), bool> = checks
+        .iter()
+        .map(|check| ((check.kind, check.identifier.clone()), check.critical))
+        .collect();
+
+    let deadline = Instant::now() + parse_info_deadline();
+    let results = latest_results(&cache, checks, query.fresh, Some(deadline)).await;
+
+    let mut total_checks = 0;
+    let mut failed_critical = Vec::new();
+    let mut failed_optional = Vec::new();
+
+    for (kind, outputs) in results {
+        for (identifier, output) in outputs {
+            total_checks += 1;
+
+            if output.success {
+                continue;
+            }
+
+            let is_critical = critical
+                .get(&(kind.as_str(), identifier.clone()))
+                .copied()
+                .unwrap_or(true);
+            let failed = FailedCheck {
+                kind: kind.clone(),
+                identifier,
+                error: output
+                    .result
+                    .get("error")
+                    .and_then(|e| e.as_str())
+                    .map(|e| e.to_string()),
+            };
+
+            if is_critical {
+                failed_critical.push(failed);
+            } else {
+                failed_optional.push(failed);
+            }
+        }
+    }
+
+    let ready = failed_critical.is_empty();
+    if ready {
+        info!(
+            event = "readiness_checked",
+            total_checks,
+            failed_optional = failed_optional.len(),
+            "Service is ready"
+        );
+    } else {
+        warn!(
+            event = "readiness_checked",
+            total_checks,
+            failed_critical = failed_critical.len(),
+            failed_optional = failed_optional.len(),
+            "Service is not ready"
+        );
+    }
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            total_checks,
+            failed_critical,
+            failed_optional,
+            shutting_down: false,
+        }),
+    )
+}
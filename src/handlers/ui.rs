@@ -1,7 +1,9 @@
+use crate::check::registry;
 use axum::{
     http::StatusCode,
     response::{Html, IntoResponse, Response},
 };
+use serde_json::json;
 
 /// UI endpoint handler
 /// Returns a beautiful HTML page that displays the info endpoint data
@@ -267,6 +269,10 @@ pub async fn ui_handler() -> Response {
             border-left: 5px solid #f97316;
         }
 
+        .check-item.other {
+            border-left: 5px solid #64748b;
+        }
+
         .check-header {
             display: flex;
             justify-content: space-between;
@@ -634,6 +640,22 @@ pub async fn ui_handler() -> Response {
     <script>
         let systemData = null;
 
+        // Registered check kinds, injected by the server
+        const CHECK_KINDS = __CHECK_KINDS__;
+
+        // Card renderers of check results, named by the dashboard hints of the kinds
+        const RENDERERS = {
+            http: renderHttpApi,
+            sql: renderSqlDatabase,
+            nosql: renderNoSqlDatabase,
+            dynamodb: renderDynamoDB,
+            s3: renderS3Bucket,
+            memorydb: renderMemoryDB,
+            secrets_manager: renderSecretsManager,
+            bedrock: renderBedrock,
+            generic: renderGenericCheck,
+        };
+
         async function loadData() {
             try {
                 const response = await fetch('/_/info');
@@ -664,56 +686,39 @@ pub async fn ui_handler() -> Response {
                 content: renderHttpRequestForm()
             });
 
-            // Count items for badges
-            const counts = {
-                environments: data.environments ? Object.keys(data.environments).length : 0,
-                http: data.http ? Object.keys(data.http).length : 0,
-                sql: data.sql ? Object.keys(data.sql).length : 0,
-                nosql: data.nosql ? Object.keys(data.nosql).length : 0,
-                s3: data.s3 ? Object.keys(data.s3).length : 0,
-                memorydb: data.memorydb ? Object.keys(data.memorydb).length : 0,
-                secrets_manager: data.secrets_manager ? Object.keys(data.secrets_manager).length : 0,
-                dynamodb: data.dynamodb ? Object.keys(data.dynamodb).length : 0,
-                bedrock: data.bedrock ? Object.keys(data.bedrock).length : 0,
-            };
-
             // Environment Variables Tab
-            if (counts.environments > 0) {
-                tabs.push({ id: 'environments', label: '🌍 Environments', count: counts.environments });
+            if (data.environments && Object.keys(data.environments).length > 0) {
+                tabs.push({ id: 'environments', label: '🌍 Environments', count: Object.keys(data.environments).length });
                 tabContents.push({
                     id: 'environments',
                     content: renderEnvironments(data.environments)
                 });
             }
 
-            // HTTP APIs Tab
-            if (counts.http > 0) {
-                tabs.push({ id: 'http', label: '🌐 HTTP APIs', count: counts.http });
-                tabContents.push({
-                    id: 'http',
-                    content: renderHttpApis(data.http)
-                });
-            }
+            // Check tabs, grouping the kinds with results by the tab of their dashboard hints
+            const checkTabs = [];
+            CHECK_KINDS.forEach(kind => {
+                const count = data[kind.kind] ? Object.keys(data[kind.kind]).length : 0;
+                if (count === 0) {
+                    return;
+                }
 
-            // Databases Tab
-            const dbCount = counts.sql + counts.nosql + counts.dynamodb;
-            if (dbCount > 0) {
-                tabs.push({ id: 'databases', label: '🗄️ Databases', count: dbCount });
-                tabContents.push({
-                    id: 'databases',
-                    content: renderDatabases(data)
-                });
-            }
+                let tab = checkTabs.find(tab => tab.id === kind.dashboard.tab.id);
+                if (!tab) {
+                    tab = { ...kind.dashboard.tab, kinds: [], count: 0 };
+                    checkTabs.push(tab);
+                }
+                tab.kinds.push(kind);
+                tab.count += count;
+            });
 
-            // AWS Services Tab
-            const awsCount = counts.s3 + counts.memorydb + counts.secrets_manager + counts.bedrock;
-            if (awsCount > 0) {
-                tabs.push({ id: 'aws', label: '☁️ AWS Services', count: awsCount });
+            checkTabs.forEach(tab => {
+                tabs.push({ id: tab.id, label: tab.label, count: tab.count });
                 tabContents.push({
-                    id: 'aws',
-                    content: renderAwsServices(data)
+                    id: tab.id,
+                    content: renderCheckTab(data, tab)
                 });
-            }
+            });
 
             if (tabs.length === 0) {
                 document.getElementById('content').innerHTML = `
@@ -777,247 +782,72 @@ pub async fn ui_handler() -> Response {
             `;
         }
 
-        function renderHttpApis(http) {
-            const stats = calculateStats(http);
+        function renderCheckTab(data, tab) {
+            return `<h2 class="section-header">${escapeHtml(tab.title)}</h2>`
+                + tab.kinds.map(kind => renderKindSection(data[kind.kind], kind)).join('');
+        }
+
+        function renderKindSection(results, { label, dashboard }) {
+            const stats = calculateStats(results);
+            const render = RENDERERS[dashboard.renderer] || renderGenericCheck;
+            const gradient = `linear-gradient(135deg, ${dashboard.colors[0]} 0%, ${dashboard.colors[1]} 100%)`;
 
             return `
-                <h2 class="section-header">HTTP API Endpoints</h2>
+                <h3 style="color: ${dashboard.colors[0]}; margin: 25px 0 15px; font-size: 1.2rem;">${dashboard.icon} ${escapeHtml(label)}</h3>
                 <div class="stats-summary">
-                    <div class="stat-card">
+                    <div class="stat-card" style="background: ${gradient};">
                         <div class="stat-number">${stats.total}</div>
-                        <div class="stat-label">Total APIs</div>
+                        <div class="stat-label">Total</div>
                     </div>
-                    <div class="stat-card">
+                    <div class="stat-card" style="background: ${gradient};">
                         <div class="stat-number">${stats.success}</div>
-                        <div class="stat-label">Healthy</div>
+                        <div class="stat-label">${escapeHtml(dashboard.success_label)}</div>
                     </div>
-                    <div class="stat-card">
+                    <div class="stat-card" style="background: ${gradient};">
                         <div class="stat-number">${stats.error}</div>
                         <div class="stat-label">Errors</div>
                     </div>
                 </div>
                 <div class="grid">
-                    ${Object.entries(http).map(([key, result]) => `
-                        <div class="check-item http">
-                            <div class="check-header">
-                                <span class="check-title">${escapeHtml(key)}</span>
-                                <span class="status-badge ${result.success ? 'status-success' : 'status-error'}">
-                                    ${result.success ? '✓ Success' : '✗ Failed'}
-                                </span>
-                            </div>
-                            <div class="check-details">
-                                <div class="detail-row">
-                                    <span class="detail-label">URL:</span>
-                                    <span class="detail-value">${escapeHtml(result.url)}</span>
-                                </div>
-                                <div class="detail-row">
-                                    <span class="detail-label">Method:</span>
-                                    <span class="detail-value">${escapeHtml(result.method)}</span>
-                                </div>
-                                ${result.status_code ? `
-                                    <div class="detail-row">
-                                        <span class="detail-label">Status Code:</span>
-                                        <span class="detail-value">${result.status_code}</span>
-                                    </div>
-                                ` : ''}
-                                ${result.error ? `
-                                    <div class="detail-row">
-                                        <span class="detail-label">Error:</span>
-                                        <span class="detail-value" style="color: #ef476f;">${escapeHtml(result.error)}</span>
-                                    </div>
-                                ` : ''}
-                            </div>
-                        </div>
-                    `).join('')}
+                    ${Object.entries(results).map(([key, result]) => render(key, result)).join('')}
                 </div>
             `;
         }
 
-        function renderDatabases(data) {
-            let html = '<h2 class="section-header">Database Connections</h2>';
-
-            // SQL Databases
-            if (data.sql && Object.keys(data.sql).length > 0) {
-                const stats = calculateStats(data.sql);
-                html += `
-                    <h3 style="color: #3b82f6; margin: 25px 0 15px; font-size: 1.2rem;">💾 SQL Databases</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #3b82f6 0%, #2563eb 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #3b82f6 0%, #2563eb 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Connected</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #3b82f6 0%, #2563eb 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.sql).map(([key, result]) => renderSqlDatabase(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            // NoSQL Databases
-            if (data.nosql && Object.keys(data.nosql).length > 0) {
-                const stats = calculateStats(data.nosql);
-                html += `
-                    <h3 style="color: #10b981; margin: 25px 0 15px; font-size: 1.2rem;">🔥 NoSQL Databases</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #10b981 0%, #059669 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #10b981 0%, #059669 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Connected</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #10b981 0%, #059669 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.nosql).map(([key, result]) => renderNoSqlDatabase(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            // DynamoDB
-            if (data.dynamodb && Object.keys(data.dynamodb).length > 0) {
-                const stats = calculateStats(data.dynamodb);
-                html += `
-                    <h3 style="color: #06b6d4; margin: 25px 0 15px; font-size: 1.2rem;">⚡ DynamoDB Tables</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #06b6d4 0%, #0891b2 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #06b6d4 0%, #0891b2 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Accessible</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #06b6d4 0%, #0891b2 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.dynamodb).map(([key, result]) => renderDynamoDB(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            return html;
-        }
-
-        function renderAwsServices(data) {
-            let html = '<h2 class="section-header">AWS Cloud Services</h2>';
-
-            // S3 Buckets
-            if (data.s3 && Object.keys(data.s3).length > 0) {
-                const stats = calculateStats(data.s3);
-                html += `
-                    <h3 style="color: #ec4899; margin: 25px 0 15px; font-size: 1.2rem;">🪣 S3 Buckets</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ec4899 0%, #db2777 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ec4899 0%, #db2777 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Accessible</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ec4899 0%, #db2777 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.s3).map(([key, result]) => renderS3Bucket(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            // MemoryDB
-            if (data.memorydb && Object.keys(data.memorydb).length > 0) {
-                const stats = calculateStats(data.memorydb);
-                html += `
-                    <h3 style="color: #8b5cf6; margin: 25px 0 15px; font-size: 1.2rem;">💜 MemoryDB Clusters</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #8b5cf6 0%, #7c3aed 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #8b5cf6 0%, #7c3aed 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Accessible</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #8b5cf6 0%, #7c3aed 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.memorydb).map(([key, result]) => renderMemoryDB(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            // Secrets Manager
-            if (data.secrets_manager && Object.keys(data.secrets_manager).length > 0) {
-                const stats = calculateStats(data.secrets_manager);
-                html += `
-                    <h3 style="color: #ef4444; margin: 25px 0 15px; font-size: 1.2rem;">🔐 Secrets Manager</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Accessible</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
-                        </div>
-                    </div>
-                    <div class="grid">
-                        ${Object.entries(data.secrets_manager).map(([key, result]) => renderSecretsManager(key, result)).join('')}
+        function renderHttpApi(key, result) {
+            return `
+                <div class="check-item http">
+                    <div class="check-header">
+                        <span class="check-title">${escapeHtml(key)}</span>
+                        <span class="status-badge ${result.success ? 'status-success' : 'status-error'}">
+                            ${result.success ? '✓ Success' : '✗ Failed'}
+                        </span>
                     </div>
-                `;
-            }
-
-            // Bedrock
-            if (data.bedrock && Object.keys(data.bedrock).length > 0) {
-                const stats = calculateStats(data.bedrock);
-                html += `
-                    <h3 style="color: #f97316; margin: 25px 0 15px; font-size: 1.2rem;">🤖 AWS Bedrock</h3>
-                    <div class="stats-summary">
-                        <div class="stat-card" style="background: linear-gradient(135deg, #f97316 0%, #ea580c 100%);">
-                            <div class="stat-number">${stats.total}</div>
-                            <div class="stat-label">Total</div>
-                        </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #f97316 0%, #ea580c 100%);">
-                            <div class="stat-number">${stats.success}</div>
-                            <div class="stat-label">Accessible</div>
+                    <div class="check-details">
+                        <div class="detail-row">
+                            <span class="detail-label">URL:</span>
+                            <span class="detail-value">${escapeHtml(result.url)}</span>
                         </div>
-                        <div class="stat-card" style="background: linear-gradient(135deg, #f97316 0%, #ea580c 100%);">
-                            <div class="stat-number">${stats.error}</div>
-                            <div class="stat-label">Errors</div>
+                        <div class="detail-row">
+                            <span class="detail-label">Method:</span>
+                            <span class="detail-value">${escapeHtml(result.method)}</span>
                         </div>
+                        ${result.status_code ? `
+                            <div class="detail-row">
+                                <span class="detail-label">Status Code:</span>
+                                <span class="detail-value">${result.status_code}</span>
+                            </div>
+                        ` : ''}
+                        ${renderDuration(result)}
+                        ${result.error ? `
+                            <div class="detail-row">
+                                <span class="detail-label">Error:</span>
+                                <span class="detail-value" style="color: #ef476f;">${escapeHtml(result.error)}</span>
+                            </div>
+                        ` : ''}
                     </div>
-                    <div class="grid">
-                        ${Object.entries(data.bedrock).map(([key, result]) => renderBedrock(key, result)).join('')}
-                    </div>
-                `;
-            }
-
-            return html;
+                </div>
+            `;
         }
 
         function renderSqlDatabase(key, result) {
@@ -1051,6 +881,7 @@ pub async fn ui_handler() -> Response {
                                 ${result.tables.length > 10 ? `<li>... and ${result.tables.length - 10} more</li>` : ''}
                             </ul>
                         ` : ''}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1080,6 +911,7 @@ pub async fn ui_handler() -> Response {
                             <span class="detail-label">Host:</span>
                             <span class="detail-value">${escapeHtml(result.host)}:${result.port}</span>
                         </div>
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1115,6 +947,7 @@ pub async fn ui_handler() -> Response {
                                 <span class="detail-value">${result.object_count}</span>
                             </div>
                         ` : ''}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1156,6 +989,7 @@ pub async fn ui_handler() -> Response {
                                 <span class="detail-value">${result.node_count}</span>
                             </div>
                         ` : ''}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1191,6 +1025,7 @@ pub async fn ui_handler() -> Response {
                                 <span class="detail-value">${escapeHtml(result.version_id)}</span>
                             </div>
                         ` : ''}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1232,6 +1067,7 @@ pub async fn ui_handler() -> Response {
                                 <span class="detail-value">${result.item_count.toLocaleString()}</span>
                             </div>
                         ` : ''}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1272,6 +1108,39 @@ pub async fn ui_handler() -> Response {
                                 ${result.models.length > 5 ? `<li>... and ${result.models.length - 5} more</li>` : ''}
                             </ul>
                         ` : ''}
+                        ${renderDuration(result)}
+                        ${result.error ? `
+                            <div class="detail-row">
+                                <span class="detail-label">Error:</span>
+                                <span class="detail-value" style="color: #ef476f;">${escapeHtml(result.error)}</span>
+                            </div>
+                        ` : ''}
+                    </div>
+                </div>
+            `;
+        }
+
+        function renderGenericCheck(key, result) {
+            // Show every scalar field of the result except the status and error
+            const fields = Object.entries(result)
+                .filter(([name, value]) => name !== 'success' && name !== 'error' && name !== 'duration_ms' && value !== null && typeof value !== 'object');
+
+            return `
+                <div class="check-item other">
+                    <div class="check-header">
+                        <span class="check-title">${escapeHtml(key)}</span>
+                        <span class="status-badge ${result.success ? 'status-success' : 'status-error'}">
+                            ${result.success ? '✓ Success' : '✗ Failed'}
+                        </span>
+                    </div>
+                    <div class="check-details">
+                        ${fields.map(([name, value]) => `
+                            <div class="detail-row">
+                                <span class="detail-label">${escapeHtml(name)}:</span>
+                                <span class="detail-value">${escapeHtml(value)}</span>
+                            </div>
+                        `).join('')}
+                        ${renderDuration(result)}
                         ${result.error ? `
                             <div class="detail-row">
                                 <span class="detail-label">Error:</span>
@@ -1283,6 +1152,19 @@ pub async fn ui_handler() -> Response {
             `;
         }
 
+        function renderDuration(result) {
+            if (result.duration_ms === undefined) {
+                return '';
+            }
+
+            return `
+                <div class="detail-row">
+                    <span class="detail-label">Duration:</span>
+                    <span class="detail-value">${result.duration_ms.toFixed(1)} ms${result.timed_out ? ' (timed out)' : ''}</span>
+                </div>
+            `;
+        }
+
         function calculateStats(data) {
             const entries = Object.values(data);
             return {
@@ -1559,5 +1441,46 @@ pub async fn ui_handler() -> Response {
 </html>
     "#;
 
+    // Let the dashboard iterate over every registered check kind
+    let check_kinds: Vec<_> = registry()
+        .iter()
+        .map(|kind| {
+            json!({
+                "kind": kind.kind(),
+                "label": kind.label(),
+                "dashboard": kind.dashboard(),
+            })
+        })
+        .collect();
+    let html = html.replace(
+        "__CHECK_KINDS__",
+        &serde_json::to_string(&check_kinds).unwrap_or_else(|_| "[]".to_string()),
+    );
+
     (StatusCode::OK, Html(html)).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[tokio::test]
+    async fn test_dashboard_renderers_exist() {
+        let body = to_bytes(ui_handler().await.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+
+        // Every kind names a card renderer of the script, and is injected with its hints
+        for kind in registry() {
+            let renderer = kind.dashboard().renderer;
+            assert!(
+                html.contains(&format!("            {}: render", renderer)),
+                "{}",
+                renderer
+            );
+        }
+        assert!(html.contains(r#""renderer":"sql""#));
+    }
+}
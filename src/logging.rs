@@ -0,0 +1,130 @@
+use opentelemetry::trace::TraceContextExt;
+use serde_json::{Map, Value};
+use std::env;
+use std::fmt;
+use tracing::{Event, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::Layer;
+use tracing_subscriber::fmt::format::{Format, Json, JsonFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Log output format (LOG_FORMAT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable single line per event (default)
+    Text,
+    /// Human-readable multi-line output
+    Pretty,
+    /// Human-readable single line without span fields
+    Compact,
+    /// One JSON object per event, with span fields and trace/span IDs
+    Json,
+}
+
+impl LogFormat {
+    /// Load the log format from LOG_FORMAT, defaults to text
+    pub fn from_env() -> Self {
+        env::var("LOG_FORMAT")
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(LogFormat::Text)
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "text" | "full" => Some(LogFormat::Text),
+            "pretty" => Some(LogFormat::Pretty),
+            "compact" => Some(LogFormat::Compact),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Create the layer writing logs to stdout in the given format
+pub fn fmt_layer<S>(format: LogFormat) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let layer = tracing_subscriber::fmt::layer();
+
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer
+            .fmt_fields(JsonFields::new())
+            .event_format(JsonWithTraceIds(
+                tracing_subscriber::fmt::format()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true),
+            ))
+            .boxed(),
+    }
+}
+
+/// JSON event format adding the OpenTelemetry trace_id and span_id of the current span
+struct JsonWithTraceIds(Format<Json>);
+
+impl<S, N> FormatEvent<S, N> for JsonWithTraceIds
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = String::new();
+        self.0.format_event(ctx, Writer::new(&mut line), event)?;
+
+        let ids = ctx.lookup_current().and_then(|span| {
+            let extensions = span.extensions();
+            let data = extensions.get::<OtelData>()?;
+
+            // Spans with a remote or local parent belong to the trace of their parent
+            let parent = data.parent_cx.span();
+            let parent = parent.span_context();
+            let trace_id = if parent.is_valid() {
+                parent.trace_id()
+            } else {
+                data.builder.trace_id?
+            };
+
+            Some((trace_id, data.builder.span_id?))
+        });
+
+        let Some((trace_id, span_id)) = ids else {
+            return writer.write_str(&line);
+        };
+
+        match serde_json::from_str::<Map<String, Value>>(&line) {
+            Ok(mut object) => {
+                object.insert("trace_id".to_string(), trace_id.to_string().into());
+                object.insert("span_id".to_string(), span_id.to_string().into());
+
+                let json = serde_json::to_string(&object).map_err(|_| fmt::Error)?;
+                writeln!(writer, "{}", json)
+            }
+            Err(_) => writer.write_str(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::parse("JSON"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse("pretty"), Some(LogFormat::Pretty));
+        assert_eq!(LogFormat::parse("compact"), Some(LogFormat::Compact));
+        assert_eq!(LogFormat::parse("text"), Some(LogFormat::Text));
+        assert_eq!(LogFormat::parse("xml"), None);
+    }
+}
@@ -1,21 +1,41 @@
 mod check;
+mod config_file;
 mod env_parser;
 mod handlers;
+mod logging;
+mod masking;
 mod metrics;
 mod models;
+mod reload;
 mod routes;
+mod scheduler;
+mod shutdown;
+mod state;
 mod telemetry;
+mod validation;
 
+use check::load_checks;
+use env_parser::{parse_shutdown_delay, parse_shutdown_grace_period, parse_strict_config};
+use logging::{LogFormat, fmt_layer};
 use metrics::create_metric_layer;
+use opentelemetry::global;
 use opentelemetry::trace::TracerProvider;
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use reload::{ActiveChecks, start_config_poller};
 use routes::create_router;
+use scheduler::{ResultCache, Scheduler};
+use shutdown::{Shutdown, wait_for_signal};
+use state::AppState;
 use std::env;
-use telemetry::{init_telemetry, ExporterType, OtelConfig, OtelProviders};
+use telemetry::{
+    ExporterType, OtelConfig, OtelProviders, init_telemetry, make_request_span, shutdown_telemetry,
+};
 use tokio::net::TcpListener;
+use tokio::time::{Instant, sleep, timeout_at};
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use validation::{log_issues, with_env_issues};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -25,8 +45,8 @@ async fn main() -> anyhow::Result<()> {
     // Load OpenTelemetry configuration
     let otel_config = OtelConfig::from_env();
 
-    // Initialize OpenTelemetry if not disabled and any exporter is enabled
-    let _otel_providers = init_otel_and_tracing(&otel_config);
+    // Initialize OpenTelemetry if not disabled, traces are propagated even without exporters
+    let otel_providers = init_otel_and_tracing(&otel_config, LogFormat::from_env());
 
     info!("Starting pmp-test-api");
     log_otel_status(&otel_config);
@@ -43,10 +63,39 @@ async fn main() -> anyhow::Result<()> {
     // Create prometheus metrics layer and state
     let (metric_layer, metrics_state) = create_metric_layer();
 
+    // Load the checks and report misconfigurations, which are fatal with STRICT_CONFIG
+    let loaded = load_checks();
+    let issues = with_env_issues(loaded.issues);
+    log_issues(&issues);
+
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    if errors > 0 && parse_strict_config() {
+        anyhow::bail!(
+            "STRICT_CONFIG is enabled and the configuration has {} error(s)",
+            errors
+        );
+    }
+
+    // Start background checks for every check with an interval
+    let results = ResultCache::default();
+    let shutdown = Shutdown::default();
+    let scheduler = Scheduler::new(results.clone(), shutdown.clone());
+    let checks = ActiveChecks::new(loaded.checks, scheduler.clone());
+
+    // Reload the checks when CONFIG_DIR or CONFIG_FILE change
+    start_config_poller(checks.clone(), shutdown.clone());
+
+    let state = AppState {
+        metrics: metrics_state,
+        checks,
+        results,
+        shutdown: shutdown.clone(),
+    };
+
     // Create router with metrics and tracing layers
-    let app = create_router(metrics_state)
+    let app = create_router(state)
         .layer(metric_layer)
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http().make_span_with(make_request_span));
 
     // Create TCP listener
     let listener = TcpListener::bind(&addr).await?;
@@ -54,40 +103,98 @@ async fn main() -> anyhow::Result<()> {
     info!("Server listening on {}", addr);
     info!("Dashboard: http://{}/", addr);
     info!("Health endpoint: http://{}/_/health", addr);
+    info!("Readiness endpoint: http://{}/_/ready", addr);
     info!("Info endpoint: http://{}/_/info", addr);
+    info!("Checks endpoint: http://{}/_/checks", addr);
+    info!("Config endpoint: http://{}/_/config", addr);
+    info!(
+        "Config validation endpoint: http://{}/_/config/validate",
+        addr
+    );
     info!("Metrics endpoint: http://{}/metrics", addr);
+    info!("Reload endpoint: POST http://{}/_/admin/reload", addr);
+
+    // Start the server, it stops accepting connections SHUTDOWN_DELAY after shutdown starts
+    let shutdown_delay = parse_shutdown_delay();
+    let server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown({
+                let shutdown = shutdown.clone();
+                async move {
+                    shutdown.triggered().await;
+                    sleep(shutdown_delay).await;
+                    info!("Stopped accepting connections");
+                }
+            })
+            .into_future(),
+    );
 
-    // Start the server
-    axum::serve(listener, app).await?;
+    wait_for_signal().await;
+
+    // Fail readiness first so that no new traffic is routed to this instance
+    shutdown.trigger();
+    let grace_period = parse_shutdown_grace_period();
+    let deadline = Instant::now() + shutdown_delay + grace_period;
+    info!(
+        delay = ?shutdown_delay,
+        grace_period = ?grace_period,
+        "Shutting down, readiness is now failing"
+    );
+
+    // Let in-flight requests (and the checks they run) finish until the deadline
+    match timeout_at(deadline, server).await {
+        Ok(result) => result??,
+        Err(_) => warn!("Grace period elapsed before in-flight requests finished"),
+    }
+
+    // Let scheduled checks in progress finish until the deadline
+    if !scheduler.drain(deadline).await {
+        warn!("Grace period elapsed before scheduled checks finished");
+    }
+
+    // Flush batched spans, metrics and logs (the providers block until exported)
+    if let Some(providers) = otel_providers {
+        tokio::task::spawn_blocking(move || shutdown_telemetry(providers)).await?;
+    }
+
+    info!("Shutdown complete");
 
     Ok(())
 }
 
-fn init_otel_and_tracing(config: &OtelConfig) -> Option<OtelProviders> {
-    if config.disabled || !config.is_any_enabled() {
-        init_standard_tracing();
+fn init_otel_and_tracing(config: &OtelConfig, log_format: LogFormat) -> Option<OtelProviders> {
+    if config.disabled {
+        init_standard_tracing(log_format);
         return None;
     }
 
     match init_telemetry(config) {
         Ok(providers) => {
-            init_tracing_with_otel(config.service_name.clone(), &providers);
+            init_tracing_with_otel(config.service_name.clone(), &providers, log_format);
+
+            // Check instruments are created from the global meter provider
+            if let Some(meter_provider) = &providers.meter_provider {
+                global::set_meter_provider(meter_provider.clone());
+            }
+
             Some(providers)
         }
         Err(e) => {
             eprintln!("Failed to initialize OpenTelemetry: {}", e);
-            init_standard_tracing();
+            init_standard_tracing(log_format);
             None
         }
     }
 }
 
-fn init_tracing_with_otel(service_name: String, providers: &OtelProviders) {
+fn init_tracing_with_otel(service_name: String, providers: &OtelProviders, log_format: LogFormat) {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| "info,pmp_test_api=debug".into());
 
-    let fmt_layer = tracing_subscriber::fmt::layer();
-    let registry = tracing_subscriber::registry().with(env_filter).with(fmt_layer);
+    let fmt_layer = fmt_layer(log_format);
+    let registry = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer);
 
     match (&providers.tracer_provider, &providers.logger_provider) {
         (Some(tracer_provider), Some(logger_provider)) => {
@@ -111,13 +218,13 @@ fn init_tracing_with_otel(service_name: String, providers: &OtelProviders) {
     }
 }
 
-fn init_standard_tracing() {
+fn init_standard_tracing(log_format: LogFormat) {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "info,pmp_test_api=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(fmt_layer(log_format))
         .init();
 }
 
@@ -135,6 +242,8 @@ fn log_otel_status(config: &OtelConfig) {
     info!(
         endpoint = %config.endpoint,
         protocol = ?config.protocol,
+        timeout = ?config.timeout,
+        sampler = ?config.sampler,
         traces = %format_exporter_status(&config.traces_exporter),
         metrics = %format_exporter_status(&config.metrics_exporter),
         logs = %format_exporter_status(&config.logs_exporter),
@@ -1,8 +1,115 @@
-use axum_prometheus::PrometheusMetricLayerBuilder;
-use axum::{
-    response::IntoResponse,
-};
-use metrics_exporter_prometheus::PrometheusHandle;
+use crate::check::CheckOutput;
+use axum::response::IntoResponse;
+use axum_prometheus::utils::SECONDS_DURATION_BUCKETS;
+use axum_prometheus::{AXUM_HTTP_REQUESTS_DURATION_SECONDS, PrometheusMetricLayerBuilder};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Histogram, Meter, ObservableGauge};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Whether the last run of a check succeeded (1) or failed (0)
+const CHECK_UP: &str = "pmp_check_up";
+
+/// Duration of check runs
+const CHECK_DURATION_SECONDS: &str = "pmp_check_duration_seconds";
+
+/// Number of failed check runs
+const CHECK_FAILURES_TOTAL: &str = "pmp_check_failures_total";
+
+/// Buckets of the check duration histogram, in seconds
+const CHECK_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// OpenTelemetry instruments for check runs
+/// Created from the global meter provider on the first check run
+struct CheckInstruments {
+    runs: Counter<u64>,
+    failures: Counter<u64>,
+    duration: Histogram<f64>,
+    status: Arc<Mutex<HashMap<(&'static str, String), bool>>>,
+    _up: ObservableGauge<u64>,
+}
+
+static CHECK_INSTRUMENTS: OnceLock<CheckInstruments> = OnceLock::new();
+
+fn check_instruments() -> &'static CheckInstruments {
+    CHECK_INSTRUMENTS.get_or_init(|| CheckInstruments::new(&global::meter("pmp-test-api")))
+}
+
+impl CheckInstruments {
+    fn new(meter: &Meter) -> Self {
+        let status: Arc<Mutex<HashMap<(&'static str, String), bool>>> = Arc::default();
+
+        // The gauge reports the outcome of the last run of every check on each collection
+        let observed = status.clone();
+        let up = meter
+            .u64_observable_gauge("pmp.check.up")
+            .with_description("Whether the last run of the check succeeded")
+            .with_callback(move |observer| {
+                let status = observed.lock().unwrap_or_else(|e| e.into_inner());
+
+                for ((kind, identifier), success) in status.iter() {
+                    observer.observe(u64::from(*success), &check_attributes(kind, identifier));
+                }
+            })
+            .build();
+
+        Self {
+            runs: meter
+                .u64_counter("pmp.check.runs")
+                .with_description("Number of check runs")
+                .build(),
+            failures: meter
+                .u64_counter("pmp.check.failures")
+                .with_description("Number of failed check runs")
+                .build(),
+            duration: meter
+                .f64_histogram("pmp.check.duration")
+                .with_description("Duration of check runs")
+                .with_unit("s")
+                .with_boundaries(CHECK_DURATION_BUCKETS.to_vec())
+                .build(),
+            status,
+            _up: up,
+        }
+    }
+
+    fn record(&self, kind: &'static str, identifier: &str, output: &CheckOutput) {
+        let attributes = check_attributes(kind, identifier);
+
+        self.runs.add(1, &attributes);
+        self.duration
+            .record(output.duration_ms / 1000.0, &attributes);
+
+        if !output.success {
+            self.failures.add(1, &attributes);
+        }
+
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((kind, identifier.to_string()), output.success);
+    }
+
+    /// Stop observing a check in the gauge, the counters and histogram keep their series
+    /// The SDK only drops the series from delta exports, cumulative exports keep its last value
+    fn remove(&self, kind: &'static str, identifier: &str) {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(kind, identifier.to_string()));
+    }
+}
+
+fn check_attributes(kind: &'static str, identifier: &str) -> [KeyValue; 2] {
+    [
+        KeyValue::new("check.kind", kind),
+        KeyValue::new("check.identifier", identifier.to_string()),
+    ]
+}
 
 /// Metrics state that holds the prometheus metric handle
 #[derive(Clone)]
@@ -11,19 +118,314 @@ pub struct MetricsState {
 }
 
 /// Create the prometheus metric layer with custom configuration
-pub fn create_metric_layer() -> (axum_prometheus::PrometheusMetricLayer<'static>, MetricsState) {
+pub fn create_metric_layer() -> (
+    axum_prometheus::PrometheusMetricLayer<'static>,
+    MetricsState,
+) {
     let (metric_layer, handle) = PrometheusMetricLayerBuilder::new()
-        .with_default_metrics()
+        .with_metrics_from_fn(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Full(AXUM_HTTP_REQUESTS_DURATION_SECONDS.to_string()),
+                    SECONDS_DURATION_BUCKETS,
+                )
+                .and_then(|builder| {
+                    builder.set_buckets_for_metric(
+                        Matcher::Full(CHECK_DURATION_SECONDS.to_string()),
+                        CHECK_DURATION_BUCKETS,
+                    )
+                })
+                .expect("Failed to configure metric buckets")
+                .install_recorder()
+                .expect("Failed to install metrics recorder")
+        })
         .build_pair();
 
+    ::metrics::describe_gauge!(CHECK_UP, "Whether the last run of the check succeeded");
+    ::metrics::describe_histogram!(
+        CHECK_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "Duration of check runs"
+    );
+    ::metrics::describe_counter!(CHECK_FAILURES_TOTAL, "Number of failed check runs");
+
     let state = MetricsState { handle };
 
     (metric_layer, state)
 }
 
+/// Record the outcome and duration of a check run
+/// Updates both the Prometheus metrics and the OpenTelemetry instruments
+pub fn record_check_metrics(kind: &'static str, identifier: &str, output: &CheckOutput) {
+    let labels = [
+        ("kind", kind.to_string()),
+        ("identifier", identifier.to_string()),
+    ];
+
+    ::metrics::gauge!(CHECK_UP, &labels).set(if output.success { 1.0 } else { 0.0 });
+    ::metrics::histogram!(CHECK_DURATION_SECONDS, &labels).record(output.duration_ms / 1000.0);
+
+    if !output.success {
+        ::metrics::counter!(CHECK_FAILURES_TOTAL, &labels).increment(1);
+    }
+
+    check_instruments().record(kind, identifier, output);
+}
+
+/// Forget the metrics of a check that is removed or replaced by a reload
+/// The check leaves the OpenTelemetry gauge, the Prometheus recorder cannot delete series so
+/// they keep their last value
+pub fn remove_check_metrics(kind: &'static str, identifier: &str) {
+    if let Some(instruments) = CHECK_INSTRUMENTS.get() {
+        instruments.remove(kind, identifier);
+    }
+}
+
 /// Handler for the /metrics endpoint
 pub async fn metrics_handler(
     axum::extract::State(state): axum::extract::State<MetricsState>,
 ) -> impl IntoResponse {
     state.handle.render()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::data::{self, Metric, ResourceMetrics};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider, Temporality};
+    use opentelemetry_sdk::runtime;
+    use opentelemetry_sdk::testing::metrics::{
+        InMemoryMetricExporter, InMemoryMetricExporterBuilder,
+    };
+    use serde_json::Value;
+
+    fn output(success: bool, duration_ms: f64) -> CheckOutput {
+        CheckOutput {
+            success,
+            result: Value::Null,
+            checked_at: Utc::now(),
+            duration_ms,
+            age: None,
+            timed_out: false,
+        }
+    }
+
+    fn has_status(kind: &'static str, identifier: &str) -> bool {
+        check_instruments()
+            .status
+            .lock()
+            .unwrap()
+            .contains_key(&(kind, identifier.to_string()))
+    }
+
+    #[test]
+    fn test_record_check_metrics() {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(CHECK_DURATION_SECONDS.to_string()),
+                CHECK_DURATION_BUCKETS,
+            )
+            .unwrap()
+            .build_recorder();
+        let handle = recorder.handle();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            record_check_metrics("http", "RECORDED", &output(false, 20.0));
+            record_check_metrics("http", "RECORDED", &output(false, 300.0));
+        });
+        let rendered = handle.render();
+        let labels = "kind=\"http\",identifier=\"RECORDED\"";
+
+        for line in [
+            format!("pmp_check_up{{{}}} 0", labels),
+            format!("pmp_check_failures_total{{{}}} 2", labels),
+            format!(
+                "pmp_check_duration_seconds_bucket{{{},le=\"0.01\"}} 0",
+                labels
+            ),
+            format!(
+                "pmp_check_duration_seconds_bucket{{{},le=\"0.025\"}} 1",
+                labels
+            ),
+            format!(
+                "pmp_check_duration_seconds_bucket{{{},le=\"0.5\"}} 2",
+                labels
+            ),
+            format!("pmp_check_duration_seconds_sum{{{}}} 0.32", labels),
+            format!("pmp_check_duration_seconds_count{{{}}} 2", labels),
+        ] {
+            assert!(
+                rendered.lines().any(|rendered| rendered == line),
+                "missing '{}' in:\n{}",
+                line,
+                rendered
+            );
+        }
+        assert!(has_status("http", "RECORDED"));
+
+        // A successful run sets the gauge without counting a failure
+        ::metrics::with_local_recorder(&recorder, || {
+            record_check_metrics("http", "RECORDED", &output(true, 5.0));
+        });
+        let rendered = handle.render();
+        assert!(rendered.contains(&format!("pmp_check_up{{{}}} 1", labels)));
+        assert!(rendered.contains(&format!("pmp_check_failures_total{{{}}} 2", labels)));
+        assert!(rendered.contains(&format!("pmp_check_duration_seconds_count{{{}}} 3", labels)));
+    }
+
+    #[test]
+    fn test_removed_checks_keep_their_prometheus_series() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            record_check_metrics("sql", "REMOVED", &output(false, 20.0));
+        });
+        remove_check_metrics("sql", "REMOVED");
+
+        // The check leaves the gauge, its Prometheus series are stale until it runs again
+        assert!(!has_status("sql", "REMOVED"));
+        assert!(
+            handle
+                .render()
+                .contains("pmp_check_up{kind=\"sql\",identifier=\"REMOVED\"} 0")
+        );
+
+        ::metrics::with_local_recorder(&recorder, || {
+            record_check_metrics("sql", "REMOVED", &output(true, 20.0));
+        });
+        assert!(has_status("sql", "REMOVED"));
+        assert!(
+            handle
+                .render()
+                .contains("pmp_check_up{kind=\"sql\",identifier=\"REMOVED\"} 1")
+        );
+    }
+
+    /// Last exported metric with the given name
+    fn find_metric<'a>(metrics: &'a [ResourceMetrics], name: &str) -> &'a Metric {
+        metrics
+            .iter()
+            .flat_map(|resource| &resource.scope_metrics)
+            .flat_map(|scope| &scope.metrics)
+            .rfind(|metric| metric.name == name)
+            .unwrap_or_else(|| panic!("missing {}", name))
+    }
+
+    /// Check kind and identifier attributes of a data point
+    fn check_of(attributes: &[KeyValue]) -> (String, String) {
+        let value = |key: &str| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.key.as_str() == key)
+                .map(|attribute| attribute.value.to_string())
+                .unwrap_or_default()
+        };
+
+        (value("check.kind"), value("check.identifier"))
+    }
+
+    fn points<T: Copy + 'static>(metric: &Metric) -> Vec<((String, String), T)> {
+        let data = metric.data.as_any();
+        let mut points: Vec<_> = match (
+            data.downcast_ref::<data::Sum<T>>(),
+            data.downcast_ref::<data::Gauge<T>>(),
+        ) {
+            (Some(sum), _) => sum
+                .data_points
+                .iter()
+                .map(|point| (check_of(&point.attributes), point.value))
+                .collect(),
+            (_, Some(gauge)) => gauge
+                .data_points
+                .iter()
+                .map(|point| (check_of(&point.attributes), point.value))
+                .collect(),
+            _ => panic!("unexpected data of {}", metric.name),
+        };
+        points.sort_by(|a, b| a.0.cmp(&b.0));
+
+        points
+    }
+
+    fn check(kind: &str, identifier: &str) -> (String, String) {
+        (kind.to_string(), identifier.to_string())
+    }
+
+    // The periodic reader exports on a runtime task, which a flush waits for
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_instruments() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone(), runtime::Tokio).build())
+            .build();
+        let instruments = CheckInstruments::new(&provider.meter("test"));
+
+        instruments.record("sql", "MAIN", &output(false, 20.0));
+        instruments.record("sql", "MAIN", &output(true, 300.0));
+        instruments.record("http", "API", &output(false, 5.0));
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        assert_eq!(
+            points::<u64>(find_metric(&metrics, "pmp.check.runs")),
+            [(check("http", "API"), 1), (check("sql", "MAIN"), 2)]
+        );
+        assert_eq!(
+            points::<u64>(find_metric(&metrics, "pmp.check.failures")),
+            [(check("http", "API"), 1), (check("sql", "MAIN"), 1)]
+        );
+        // The gauge callback reports the last run of every check
+        assert_eq!(
+            points::<u64>(find_metric(&metrics, "pmp.check.up")),
+            [(check("http", "API"), 0), (check("sql", "MAIN"), 1)]
+        );
+
+        let duration = find_metric(&metrics, "pmp.check.duration");
+        assert_eq!(duration.unit, "s");
+        let histogram = duration
+            .data
+            .as_any()
+            .downcast_ref::<data::Histogram<f64>>()
+            .unwrap();
+        let main = histogram
+            .data_points
+            .iter()
+            .find(|point| check_of(&point.attributes) == check("sql", "MAIN"))
+            .unwrap();
+        assert_eq!(main.count, 2);
+        assert!((main.sum - 0.32).abs() < 1e-9);
+        assert_eq!(main.bounds, CHECK_DURATION_BUCKETS);
+    }
+
+    // Cumulative exports keep the last value of unobserved gauge series, only delta exports
+    // drop them
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_removed_checks_leave_the_gauge() {
+        let exporter = InMemoryMetricExporterBuilder::new()
+            .with_temporality(Temporality::Delta)
+            .build();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone(), runtime::Tokio).build())
+            .build();
+        let instruments = CheckInstruments::new(&provider.meter("test"));
+
+        instruments.record("sql", "MAIN", &output(true, 20.0));
+        instruments.record("http", "API", &output(false, 5.0));
+        instruments.remove("http", "API");
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        assert_eq!(
+            points::<u64>(find_metric(&metrics, "pmp.check.up")),
+            [(check("sql", "MAIN"), 1)]
+        );
+        // The counters keep the series of the removed check
+        assert_eq!(
+            points::<u64>(find_metric(&metrics, "pmp.check.runs")),
+            [(check("http", "API"), 1), (check("sql", "MAIN"), 1)]
+        );
+    }
+}
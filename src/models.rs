@@ -1,43 +1,122 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::check::CheckResults;
+use crate::masking::{mask_optional_secret, mask_secret, mask_sensitive_values, mask_url};
+use crate::validation::ConfigIssue;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
 /// Main response structure for the /_/info endpoint
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 pub struct InfoResponse {
     /// All environment variables found
     pub environments: HashMap<String, String>,
 
-    /// SQL database check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sql: Option<HashMap<String, SqlCheckResult>>,
+    /// Check results keyed by kind (e.g. "sql", "http"), then by identifier.
+    /// Kinds without any configured check are omitted.
+    #[serde(flatten)]
+    pub checks: CheckResults,
+}
 
-    /// NoSQL database check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub nosql: Option<HashMap<String, NoSqlCheckResult>>,
+/// Response structure for the /_/ready endpoint
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// Whether every critical check succeeded
+    pub ready: bool,
 
-    /// HTTP API check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub http: Option<HashMap<String, HttpCheckResult>>,
+    /// Number of checks evaluated
+    pub total_checks: usize,
 
-    /// S3 bucket check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub s3: Option<HashMap<String, S3CheckResult>>,
+    /// Critical checks that failed
+    pub failed_critical: Vec<FailedCheck>,
 
-    /// MemoryDB check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memorydb: Option<HashMap<String, MemoryDBCheckResult>>,
+    /// Optional checks that failed (these do not affect readiness)
+    pub failed_optional: Vec<FailedCheck>,
 
-    /// AWS Secrets Manager check results, keyed by identifier
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub secrets_manager: Option<HashMap<String, SecretsManagerCheckResult>>,
+    /// Whether the service is shutting down (checks are not evaluated then)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub shutting_down: bool,
+}
+
+/// A failed check reported by the /_/ready endpoint
+#[derive(Debug, Serialize)]
+pub struct FailedCheck {
+    /// Check kind (e.g. "sql")
+    pub kind: String,
+
+    /// Check identifier
+    pub identifier: String,
 
-    /// DynamoDB check results, keyed by identifier
+    /// Error message reported by the check
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub dynamodb: Option<HashMap<String, DynamoDBCheckResult>>,
+    pub error: Option<String>,
+}
+
+/// Query parameters of the /_/info endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct InfoQuery {
+    /// Run every check live instead of serving cached results
+    #[serde(default)]
+    pub fresh: bool,
+}
 
-    /// Bedrock check results, keyed by identifier
+/// Error body returned by endpoints that can fail
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    /// Error message
+    pub error: String,
+}
+
+/// Effective configuration of a check, as parsed from every configuration source
+#[derive(Debug, Serialize)]
+pub struct CheckConfigResponse {
+    /// Parsed configuration of the check, with secrets masked
+    pub config: serde_json::Value,
+
+    /// Scheduler interval, absent for checks that only run on request
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub bedrock: Option<HashMap<String, BedrockCheckResult>>,
+    pub interval: Option<String>,
+
+    /// Maximum duration of a single run
+    pub timeout: String,
+
+    /// Whether a failure makes the service not ready
+    pub critical: bool,
+
+    /// Configuration fields and settings that were not set and use their default
+    pub defaults: Vec<String>,
+}
+
+/// Result of validating the configuration
+#[derive(Debug, Serialize)]
+pub struct ValidationResponse {
+    /// Whether the configuration has no error (warnings are allowed)
+    pub valid: bool,
+
+    /// Whether errors are fatal at startup and reject reloads (STRICT_CONFIG)
+    pub strict: bool,
+
+    /// Number of errors
+    pub errors: usize,
+
+    /// Number of warnings
+    pub warnings: usize,
+
+    /// Every issue found, errors first
+    pub issues: Vec<ConfigIssue>,
+}
+
+/// Checks affected by a configuration reload, as "kind/identifier"
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ReloadResponse {
+    /// Checks that were not configured before
+    pub added: Vec<String>,
+
+    /// Checks that are no longer configured
+    pub removed: Vec<String>,
+
+    /// Checks whose parameters or settings changed
+    pub changed: Vec<String>,
 }
 
 /// Result of checking a SQL database connection
@@ -58,15 +137,216 @@ pub struct SqlCheckResult {
     /// Database name
     pub database: String,
 
+    /// Server version (e.g. "16.2"), the library version for SQLite
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// State of the server as seen by the check user (only if connection successful, PostgreSQL
+    /// and MySQL)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<SqlServerStatus>,
+
     /// List of tables found (only if connection successful)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tables: Option<Vec<String>>,
 
+    /// Encryption of the session, as reported by the server (only if connection successful)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<SqlTlsStatus>,
+
+    /// Comparison of the tables with the expected schema (only if SQL_{id}_EXPECT_TABLES or
+    /// SQL_{id}_SCHEMA_MANIFEST is set and tables were listed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SqlSchemaResult>,
+
+    /// Result of the validation query (only if SQL_{id}_QUERY is set and tables were listed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<SqlQueryResult>,
+
+    /// Result of the write probe (only if SQL_{id}_WRITE_PROBE is enabled and tables were listed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_probe: Option<SqlWriteProbeResult>,
+
+    /// Duration of each phase of the check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<SqlTimings>,
+
     /// Error message if connection failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// Encryption of a SQL session
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlTlsStatus {
+    /// Whether the connection is encrypted
+    pub encrypted: bool,
+
+    /// Negotiated TLS version (e.g. "TLSv1.3")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Negotiated cipher suite
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cipher: Option<String>,
+}
+
+/// State of a SQL server, each field is omitted when it cannot be retrieved (e.g. missing
+/// privileges)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SqlServerStatus {
+    /// User the check is connected as (e.g. "app@%" for MySQL)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_user: Option<String>,
+
+    /// Privileges of the user on the database, including global privileges for MySQL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileges: Option<Vec<String>>,
+
+    /// Client connections currently open on the server, including the check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connections: Option<u64>,
+
+    /// Maximum number of client connections of the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u64>,
+
+    /// Size of the database on disk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_size_bytes: Option<u64>,
+
+    /// Whether the session cannot write (e.g. on a replica, or with read_only set)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+
+    /// Replication role of the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<SqlReplicationRole>,
+
+    /// How far a replica is behind its primary (only for replicas, absent when replication is
+    /// stopped)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replica_lag_seconds: Option<f64>,
+}
+
+/// Replication role of a SQL server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlReplicationRole {
+    Primary,
+    Replica,
+}
+
+/// Duration of the phases of a SQL check, in milliseconds
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlTimings {
+    /// Time to open the connection (including authentication)
+    pub connect_ms: f64,
+
+    /// Time to run the table listing query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_ms: Option<f64>,
+
+    /// Time to run the validation query (SQL_{id}_QUERY)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_query_ms: Option<f64>,
+
+    /// Time to run the write probe (SQL_{id}_WRITE_PROBE)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_probe_ms: Option<f64>,
+}
+
+/// Differences between the tables of a SQL database and the expected schema
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SqlSchemaResult {
+    /// Whether every expected table and column exists with the expected type
+    pub success: bool,
+
+    /// Expected tables that do not exist
+    pub missing_tables: Vec<String>,
+
+    /// Expected columns that do not exist, as `table.column`
+    pub missing_columns: Vec<String>,
+
+    /// Columns whose type is not the expected one
+    pub type_mismatches: Vec<SqlTypeMismatch>,
+
+    /// Summary of the differences, or why the schema could not be compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Column of a SQL table whose type is not the expected one
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SqlTypeMismatch {
+    /// Column, as `table.column`
+    pub column: String,
+
+    /// Type from the schema manifest
+    pub expected: String,
+
+    /// Type reported by the database
+    pub actual: String,
+}
+
+/// Result of the write probe of a SQL check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlWriteProbeResult {
+    /// Whether every step succeeded
+    pub success: bool,
+
+    /// Table the probe row was written to
+    pub table: String,
+
+    /// Steps run, up to the first failing one
+    pub steps: Vec<SqlProbeStep>,
+
+    /// Failing step and its error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Step of the write probe of a SQL check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlProbeStep {
+    /// Name of the step: create_table, begin, insert, select, delete or commit (and connect
+    /// for SQLite, which writes on a separate connection)
+    pub name: String,
+
+    /// Whether the step succeeded
+    pub success: bool,
+
+    /// Duration of the step
+    pub duration_ms: f64,
+
+    /// Error of the step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of the validation query of a SQL check
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlQueryResult {
+    /// Whether the query succeeded and its result matches the expectations
+    pub success: bool,
+
+    /// Column names
+    pub columns: Vec<String>,
+
+    /// First rows returned, up to SQL_{id}_MAX_ROWS
+    pub rows: Vec<Vec<Value>>,
+
+    /// Total number of rows returned
+    pub row_count: u64,
+
+    /// Whether rows were left out of `rows`
+    pub truncated: bool,
+
+    /// Error message if the query failed or its result does not match the expectations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Result of checking a NoSQL database connection
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NoSqlCheckResult {
@@ -86,11 +366,26 @@ pub struct NoSqlCheckResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub info: Option<HashMap<String, String>>,
 
+    /// Duration of each phase of the check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<NoSqlTimings>,
+
     /// Error message if connection failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// Duration of the phases of a NoSQL check, in milliseconds
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoSqlTimings {
+    /// Time to open the connection (including authentication)
+    pub connect_ms: f64,
+
+    /// Round-trip time of a PING command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_ms: Option<f64>,
+}
+
 /// Result of checking an HTTP API
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HttpCheckResult {
@@ -115,39 +410,234 @@ pub struct HttpCheckResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_body: Option<String>,
 
+    /// Duration of each phase of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<HttpTimings>,
+
     /// Error message if request failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// Duration of the phases of an HTTP check, in milliseconds
+/// DNS and connect are measured on the first connection of the request (to the proxy, if any)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HttpTimings {
+    /// Time to resolve the host name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<f64>,
+
+    /// Time to open the connection, including the TLS handshake for HTTPS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>,
+
+    /// Time from the connection being ready until the headers of the final response are
+    /// received, redirects included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_byte_ms: Option<f64>,
+}
+
 /// Parsed SQL database configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SqlConfig {
     pub identifier: String,
     pub driver: String,
     pub host: String,
     pub port: u16,
     pub user: String,
+    #[serde(serialize_with = "mask_secret")]
     pub password: String,
     pub database: String,
+
+    /// Driver connection options, from the URL query and SQL_{id}_OPTIONS
+    #[serde(serialize_with = "mask_sensitive_values")]
+    pub options: BTreeMap<String, String>,
+
+    /// TLS mode, the driver default or the TLS option of the URL when unset
+    pub tls_mode: Option<SqlTlsMode>,
+
+    /// Path of the CA certificates used to verify the server
+    pub tls_ca: Option<String>,
+
+    /// Path of the client certificate
+    pub tls_cert: Option<String>,
+
+    /// Path of the client private key
+    pub tls_key: Option<String>,
+
+    /// Whether to retrieve the state of the server (PostgreSQL and MySQL)
+    pub server_status: bool,
+
+    /// Schemas whose tables are listed, the driver default when empty
+    pub schemas: Vec<String>,
+
+    /// Tables that must exist, named as in the table listing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expect_tables: Vec<String>,
+
+    /// Path of the YAML or TOML file of the expected tables, columns and types
+    pub schema_manifest: Option<String>,
+
+    /// Read-only validation query
+    pub query: Option<String>,
+
+    /// Expected number of rows returned by the query
+    pub expect_rows: Option<RowCountExpectation>,
+
+    /// Expected value of the first column of the first row
+    pub expect_equals: Option<String>,
+
+    /// Text expected in the value of the first column of the first row
+    pub expect_contains: Option<String>,
+
+    /// Maximum number of rows included in the result
+    pub max_rows: usize,
+
+    /// Whether to check that the database accepts writes, with a row written to `probe_table`
+    pub write_probe: bool,
+
+    /// Table of the write probe, created if absent
+    pub probe_table: String,
+}
+
+/// Expected number of rows, e.g. `3` or `>= 1` (SQL_{id}_EXPECT_ROWS)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowCountExpectation {
+    pub comparison: Comparison,
+    pub count: u64,
+}
+
+/// Comparison operator of a [`RowCountExpectation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl RowCountExpectation {
+    /// Parse a number, optionally preceded by `=`, `!=`, `<`, `<=`, `>` or `>=`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        // Two-character operators first, so that `>=` is not read as `>`
+        let (comparison, count) = [
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("=", Comparison::Equal),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ]
+        .into_iter()
+        .find_map(|(operator, comparison)| {
+            value
+                .strip_prefix(operator)
+                .map(|count| (comparison, count))
+        })
+        .unwrap_or((Comparison::Equal, value));
+
+        Some(Self {
+            comparison,
+            count: count.trim().parse().ok()?,
+        })
+    }
+
+    pub fn matches(&self, count: u64) -> bool {
+        match self.comparison {
+            Comparison::Equal => count == self.count,
+            Comparison::NotEqual => count != self.count,
+            Comparison::Less => count < self.count,
+            Comparison::LessOrEqual => count <= self.count,
+            Comparison::Greater => count > self.count,
+            Comparison::GreaterOrEqual => count >= self.count,
+        }
+    }
+}
+
+impl fmt::Display for RowCountExpectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self.comparison {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+        write!(f, "{} {}", operator, self.count)
+    }
+}
+
+impl Serialize for RowCountExpectation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// TLS mode of a SQL connection (SQL_{id}_TLS_MODE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SqlTlsMode {
+    /// Never encrypt
+    Disable,
+    /// Encrypt if the server supports it
+    Prefer,
+    /// Always encrypt, without verifying the server certificate
+    Require,
+    /// Always encrypt and verify the server certificate against the CA
+    VerifyCa,
+    /// Always encrypt, verify the server certificate and that it matches the host
+    VerifyFull,
+}
+
+impl SqlTlsMode {
+    /// Accepted values of SQL_{id}_TLS_MODE
+    pub const NAMES: &'static [&'static str] =
+        &["disable", "prefer", "require", "verify-ca", "verify-full"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "disable" => Some(Self::Disable),
+            "prefer" => Some(Self::Prefer),
+            "require" => Some(Self::Require),
+            "verify-ca" => Some(Self::VerifyCa),
+            "verify-full" => Some(Self::VerifyFull),
+            _ => None,
+        }
+    }
 }
 
 /// Parsed NoSQL database configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct NoSqlConfig {
     pub identifier: String,
     pub driver: String,
     pub host: String,
     pub port: u16,
+    pub user: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub password: Option<String>,
+
+    /// Database number
+    pub database: i64,
+
+    /// Client connection options, from the URL query and NOSQL_{id}_OPTIONS
+    #[serde(serialize_with = "mask_sensitive_values")]
+    pub options: BTreeMap<String, String>,
 }
 
 /// Parsed HTTP API configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HttpConfig {
     pub identifier: String,
+    #[serde(serialize_with = "mask_url")]
     pub url: String,
     pub method: String,
+    #[serde(serialize_with = "mask_sensitive_values")]
     pub headers: HashMap<String, String>,
 }
 
@@ -206,22 +696,24 @@ pub struct MemoryDBCheckResult {
 }
 
 /// Parsed S3 bucket configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct S3Config {
     pub identifier: String,
     pub region: String,
     pub bucket: String,
     pub access_key_id: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub secret_access_key: Option<String>,
 }
 
 /// Parsed MemoryDB configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MemoryDBConfig {
     pub identifier: String,
     pub region: String,
     pub cluster: String,
     pub access_key_id: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub secret_access_key: Option<String>,
 }
 
@@ -302,31 +794,34 @@ pub struct BedrockCheckResult {
 }
 
 /// Parsed AWS Secrets Manager configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SecretsManagerConfig {
     pub identifier: String,
     pub region: String,
     pub secret_name: String,
     pub access_key_id: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub secret_access_key: Option<String>,
 }
 
 /// Parsed DynamoDB configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DynamoDBConfig {
     pub identifier: String,
     pub region: String,
     pub table: String,
     pub access_key_id: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub secret_access_key: Option<String>,
 }
 
 /// Parsed AWS Bedrock configuration from environment variables
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BedrockConfig {
     pub identifier: String,
     pub region: String,
     pub access_key_id: Option<String>,
+    #[serde(serialize_with = "mask_optional_secret")]
     pub secret_access_key: Option<String>,
 }
 
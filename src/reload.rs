@@ -0,0 +1,294 @@
+use crate::check::{CheckInstance, load_checks};
+use crate::env_parser::{parse_config_reload_interval, parse_strict_config};
+use crate::models::ReloadResponse;
+use crate::scheduler::Scheduler;
+use crate::shutdown::Shutdown;
+use crate::validation::log_issues;
+use std::env;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tokio::task::spawn_blocking;
+use tokio::time::{MissedTickBehavior, interval};
+use tracing::{debug, error, info};
+
+/// Checks currently served by the endpoints and run by the scheduler
+/// The whole set is swapped at once when the configuration is reloaded
+#[derive(Clone)]
+pub struct ActiveChecks {
+    checks: Arc<RwLock<Arc<Vec<CheckInstance>>>>,
+    scheduler: Scheduler,
+
+    /// Serializes reloads triggered by the poller and the admin endpoint
+    reloading: Arc<Mutex<()>>,
+}
+
+impl ActiveChecks {
+    /// Activate the given checks and schedule those that have an interval
+    pub fn new(checks: Vec<CheckInstance>, scheduler: Scheduler) -> Self {
+        let scheduled = scheduler.schedule(&checks);
+        info!("Scheduled {} background checks", scheduled);
+
+        Self {
+            checks: Arc::new(RwLock::new(Arc::new(checks))),
+            scheduler,
+            reloading: Arc::default(),
+        }
+    }
+
+    /// Snapshot of the active checks
+    pub fn current(&self) -> Arc<Vec<CheckInstance>> {
+        self.checks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Parse the configuration again and replace the active checks
+    /// The active checks are kept when a configuration source cannot be loaded or, with
+    /// STRICT_CONFIG, when the configuration has any error
+    /// The configuration is read and parsed on a blocking thread, off the async runtime
+    pub async fn reload(&self) -> Result<ReloadResponse, String> {
+        let _reloading = self.reloading.lock().await;
+
+        let loaded = spawn_blocking(load_checks)
+            .await
+            .map_err(|e| format!("Failed to load the checks: {}", e))?;
+        log_issues(&loaded.issues);
+
+        let rejected = if parse_strict_config() {
+            loaded
+                .issues
+                .iter()
+                .filter(|issue| issue.is_error())
+                .map(|issue| issue.to_string())
+                .collect()
+        } else {
+            loaded.errors
+        };
+
+        if !rejected.is_empty() {
+            let error = rejected.join("; ");
+            error!(
+                event = "checks_reload_failed",
+                error = %error,
+                "Invalid configuration, keeping the active checks"
+            );
+            return Err(error);
+        }
+
+        let previous = {
+            let mut checks = self.checks.write().unwrap_or_else(|e| e.into_inner());
+            std::mem::replace(&mut *checks, Arc::new(loaded.checks))
+        };
+        let current = self.current();
+
+        // Unchanged checks keep their task, so their schedule and cached result survive
+        for check in previous.iter() {
+            if !contains_same(&current, check) {
+                self.scheduler.unschedule(check.kind, &check.identifier);
+            }
+        }
+
+        let updated: Vec<_> = current
+            .iter()
+            .filter(|check| !contains_same(&previous, check))
+            .cloned()
+            .collect();
+        self.scheduler.schedule(&updated);
+
+        let diff = diff_checks(&previous, &current);
+        info!(
+            event = "checks_reloaded",
+            total_checks = current.len(),
+            added = ?diff.added,
+            removed = ?diff.removed,
+            changed = ?diff.changed,
+            "Reloaded checks"
+        );
+
+        Ok(diff)
+    }
+}
+
+/// Compare two sets of checks by kind and identifier
+fn diff_checks(previous: &[CheckInstance], current: &[CheckInstance]) -> ReloadResponse {
+    let name = |check: &CheckInstance| format!("{}/{}", check.kind, check.identifier);
+    let mut diff = ReloadResponse::default();
+
+    for check in current {
+        match find(previous, check) {
+            None => diff.added.push(name(check)),
+            Some(old) if !same_settings(old, check) => diff.changed.push(name(check)),
+            Some(_) => {}
+        }
+    }
+
+    for check in previous {
+        if find(current, check).is_none() {
+            diff.removed.push(name(check));
+        }
+    }
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+
+    diff
+}
+
+fn find<'a>(checks: &'a [CheckInstance], check: &CheckInstance) -> Option<&'a CheckInstance> {
+    checks
+        .iter()
+        .find(|other| other.kind == check.kind && other.identifier == check.identifier)
+}
+
+fn contains_same(checks: &[CheckInstance], check: &CheckInstance) -> bool {
+    find(checks, check).is_some_and(|other| same_settings(other, check))
+}
+
+fn same_settings(a: &CheckInstance, b: &CheckInstance) -> bool {
+    a.params == b.params
+        && a.interval == b.interval
+        && a.timeout == b.timeout
+        && a.critical == b.critical
+}
+
+/// Poll CONFIG_DIR and CONFIG_FILE every CONFIG_RELOAD_INTERVAL and reload the checks when
+/// their content changes, until shutdown
+/// There are no file system notifications: the content is hashed on every tick, which also
+/// catches files swapped through symlinks (e.g. Kubernetes ConfigMap volumes)
+pub fn start_config_poller(checks: ActiveChecks, shutdown: Shutdown) {
+    let paths: Arc<[String]> = ["CONFIG_DIR", "CONFIG_FILE"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .filter(|path| !path.is_empty())
+        .collect();
+    let period = parse_config_reload_interval();
+
+    if paths.is_empty() || period.is_zero() {
+        return;
+    }
+
+    info!(paths = ?paths, interval = ?period, "Polling configuration for changes");
+
+    tokio::spawn(async move {
+        let mut last = fingerprint(&paths).await;
+        let mut ticker = interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+
+            let current = fingerprint(&paths).await;
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            info!("Configuration changed, reloading checks");
+            // Failures are logged by reload, the next change is retried
+            let _ = checks.reload().await;
+        }
+
+        debug!("Configuration poller stopped");
+    });
+}
+
+/// Hash the content of the given files and of the files in the given directories, on a
+/// blocking thread since every file is read
+async fn fingerprint(paths: &Arc<[String]>) -> Option<u64> {
+    let paths = paths.clone();
+    spawn_blocking(move || hash_files(&paths)).await.ok()
+}
+
+fn hash_files(paths: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for path in paths {
+        let path = Path::new(path);
+
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                let mut files: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+                files.sort();
+
+                for file in files {
+                    file.hash(&mut hasher);
+                    fs::read(&file).ok().hash(&mut hasher);
+                }
+            }
+            Err(_) => fs::read(path).ok().hash(&mut hasher),
+        }
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::load_checks_from;
+
+    fn checks(pairs: &[(&str, &str)]) -> Vec<CheckInstance> {
+        load_checks_from(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+        .checks
+    }
+
+    #[test]
+    fn test_diff_checks() {
+        let previous = checks(&[
+            ("SQL_A_DRIVER", "postgres"),
+            ("SQL_B_DRIVER", "postgres"),
+            ("SQL_C_DRIVER", "postgres"),
+            ("SQL_C_INTERVAL", "30s"),
+        ]);
+        let current = checks(&[
+            ("SQL_B_DRIVER", "mysql"),
+            ("SQL_C_DRIVER", "postgres"),
+            ("SQL_C_INTERVAL", "30s"),
+            ("SQL_D_DRIVER", "postgres"),
+        ]);
+
+        assert_eq!(
+            diff_checks(&previous, &current),
+            ReloadResponse {
+                added: vec!["sql/D".to_string()],
+                removed: vec!["sql/A".to_string()],
+                changed: vec!["sql/B".to_string()],
+            }
+        );
+        assert_eq!(diff_checks(&current, &current), ReloadResponse::default());
+    }
+
+    #[tokio::test]
+    async fn test_fingerprint_follows_content() {
+        let dir = std::env::temp_dir().join(format!("pmp-fingerprint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("checks.env"), "SQL_A_DRIVER=postgres").unwrap();
+        let paths: Arc<[String]> = Arc::new([dir.to_string_lossy().into_owned()]);
+
+        let first = fingerprint(&paths).await;
+        assert_eq!(fingerprint(&paths).await, first);
+
+        fs::write(dir.join("checks.env"), "SQL_A_DRIVER=mysql").unwrap();
+        let changed = fingerprint(&paths).await;
+        assert_ne!(changed, first);
+
+        fs::write(dir.join("extra.env"), "").unwrap();
+        assert_ne!(fingerprint(&paths).await, changed);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
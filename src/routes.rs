@@ -1,17 +1,29 @@
-use crate::handlers::{execute_http_request, health_handler, info_handler, ui_handler};
-use crate::metrics::{metrics_handler, MetricsState};
+use crate::handlers::{
+    config_handler, execute_http_request, health_handler, info_handler, kind_checks_handler,
+    list_checks_handler, ready_handler, reload_handler, single_check_handler, ui_handler,
+    validate_config_handler,
+};
+use crate::metrics::metrics_handler;
+use crate::state::AppState;
 use axum::{
     Router,
     routing::{get, post},
 };
 
 /// Configure and return the application router
-pub fn create_router(metrics_state: MetricsState) -> Router {
+pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/", get(ui_handler))
         .route("/api/http-client", post(execute_http_request))
         .route("/_/health", get(health_handler))
+        .route("/_/ready", get(ready_handler))
         .route("/_/info", get(info_handler))
+        .route("/_/checks", get(list_checks_handler))
+        .route("/_/checks/:kind", get(kind_checks_handler))
+        .route("/_/checks/:kind/:identifier", get(single_check_handler))
+        .route("/_/config", get(config_handler))
+        .route("/_/config/validate", get(validate_config_handler))
+        .route("/_/admin/reload", post(reload_handler))
         .route("/metrics", get(metrics_handler))
-        .with_state(metrics_state)
+        .with_state(state)
 }
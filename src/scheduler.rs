@@ -0,0 +1,184 @@
+use crate::check::{CheckInstance, CheckOutput, CheckResults, run_checks};
+use crate::metrics::remove_check_metrics;
+use crate::shutdown::Shutdown;
+use chrono::Utc;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior, interval, timeout_at};
+use tracing::{Instrument, debug, info, info_span};
+
+/// Latest results of scheduled checks, keyed by (kind, identifier)
+#[derive(Clone, Default)]
+pub struct ResultCache {
+    results: Arc<RwLock<HashMap<(String, String), CheckOutput>>>,
+}
+
+impl ResultCache {
+    /// Get the latest result of a check, with its age filled in
+    pub fn get(&self, kind: &str, identifier: &str) -> Option<CheckOutput> {
+        let results = self.results.read().unwrap_or_else(|e| e.into_inner());
+        let mut output = results
+            .get(&(kind.to_string(), identifier.to_string()))
+            .cloned()?;
+
+        let age = (Utc::now() - output.checked_at).num_seconds().max(0);
+        output.age = Some(age as u64);
+
+        Some(output)
+    }
+
+    /// Store the latest result of a check
+    pub fn store(&self, kind: &str, identifier: &str, output: CheckOutput) {
+        let mut results = self.results.write().unwrap_or_else(|e| e.into_inner());
+        results.insert((kind.to_string(), identifier.to_string()), output);
+    }
+
+    /// Forget the latest result of a check
+    pub fn remove(&self, kind: &str, identifier: &str) {
+        let mut results = self.results.write().unwrap_or_else(|e| e.into_inner());
+        results.remove(&(kind.to_string(), identifier.to_string()));
+    }
+}
+
+/// Scheduled tasks, keyed by (kind, identifier)
+type Tasks = HashMap<(&'static str, String), JoinHandle<()>>;
+
+/// Background tasks running checks that have an interval
+/// Tasks stop scheduling runs on shutdown, a run in progress is completed first
+#[derive(Clone)]
+pub struct Scheduler {
+    tasks: Arc<Mutex<Tasks>>,
+    cache: ResultCache,
+    shutdown: Shutdown,
+}
+
+impl Scheduler {
+    pub fn new(cache: ResultCache, shutdown: Shutdown) -> Self {
+        Self {
+            tasks: Arc::default(),
+            cache,
+            shutdown,
+        }
+    }
+
+    /// Spawn a background task for every check that has an interval, replacing the task of a
+    /// check with the same kind and identifier
+    /// Returns the number of scheduled checks
+    pub fn schedule(&self, checks: &[CheckInstance]) -> usize {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        let mut scheduled = 0;
+
+        for check in checks {
+            // A zero period would make the interval panic in the task
+            let Some(period) = check.interval.filter(|period| !period.is_zero()) else {
+                continue;
+            };
+
+            info!(
+                kind = check.kind,
+                identifier = %check.identifier,
+                interval = ?period,
+                "Scheduling check"
+            );
+
+            let check = check.clone();
+            let cache = self.cache.clone();
+            let shutdown = self.shutdown.clone();
+            let span =
+                info_span!("scheduled_check", kind = check.kind, identifier = %check.identifier);
+            let key = (check.kind, check.identifier.clone());
+
+            let task = tokio::spawn(
+                async move {
+                    let mut ticker = interval(period);
+                    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+                    loop {
+                        tokio::select! {
+                            _ = ticker.tick() => {}
+                            _ = shutdown.triggered() => break,
+                        }
+
+                        let output = check.run().await;
+                        debug!(success = output.success, "Scheduled check completed");
+                        cache.store(check.kind, &check.identifier, output);
+                    }
+
+                    debug!("Scheduled check stopped");
+                }
+                .instrument(span),
+            );
+
+            if let Some(previous) = tasks.insert(key, task) {
+                previous.abort();
+            }
+            scheduled += 1;
+        }
+
+        scheduled
+    }
+
+    /// Stop the task of a check and forget its latest result and metrics
+    pub fn unschedule(&self, kind: &'static str, identifier: &str) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(task) = tasks.remove(&(kind, identifier.to_string())) {
+            debug!(kind, identifier, "Unscheduling check");
+            task.abort();
+        }
+
+        self.cache.remove(kind, identifier);
+        remove_check_metrics(kind, identifier);
+    }
+
+    /// Wait for every task to stop after shutdown, aborting those still running at the deadline
+    /// Returns whether every task stopped in time
+    pub async fn drain(&self, deadline: Instant) -> bool {
+        let mut tasks: Vec<_> = {
+            let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+            tasks.drain().map(|(_, task)| task).collect()
+        };
+
+        if timeout_at(deadline, join_all(tasks.iter_mut()))
+            .await
+            .is_err()
+        {
+            tasks.iter().for_each(|task| task.abort());
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Resolve the results of the given checks, serving cached results when available
+/// Checks without a cached result (or all of them, when `fresh` is set) are run live
+/// until the deadline
+pub async fn latest_results(
+    cache: &ResultCache,
+    checks: Vec<CheckInstance>,
+    fresh: bool,
+    deadline: Option<Instant>,
+) -> CheckResults {
+    let mut cached = Vec::new();
+    let mut live = Vec::new();
+
+    for check in checks {
+        match cache.get(check.kind, &check.identifier) {
+            Some(output) if !fresh => cached.push((check.kind, check.identifier, output)),
+            _ => live.push(check),
+        }
+    }
+
+    let mut results = run_checks(live, deadline).await;
+    for (kind, identifier, output) in cached {
+        results
+            .entry(kind.to_string())
+            .or_default()
+            .insert(identifier, output);
+    }
+
+    results
+}
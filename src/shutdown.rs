@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use tokio::signal;
+use tokio::sync::watch;
+use tracing::info;
+
+/// Shutdown flag shared by the server, the scheduler and the readiness endpoint
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        let (sender, _) = watch::channel(false);
+
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+}
+
+impl Shutdown {
+    /// Start shutting down
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Whether shutdown has started
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Wait until shutdown has started
+    pub async fn triggered(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Wait for SIGTERM or SIGINT
+pub async fn wait_for_signal() {
+    let interrupt = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_trigger() {
+        let shutdown = Shutdown::default();
+        assert!(!shutdown.is_triggered());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.triggered().await }
+        });
+
+        shutdown.trigger();
+
+        assert!(shutdown.is_triggered());
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter should be woken up")
+            .unwrap();
+    }
+}
@@ -0,0 +1,45 @@
+use crate::metrics::MetricsState;
+use crate::reload::ActiveChecks;
+use crate::scheduler::ResultCache;
+use crate::shutdown::Shutdown;
+use axum::extract::FromRef;
+
+/// Shared application state
+#[derive(Clone)]
+pub struct AppState {
+    /// Prometheus metrics handle
+    pub metrics: MetricsState,
+
+    /// Checks currently configured
+    pub checks: ActiveChecks,
+
+    /// Latest results of scheduled checks
+    pub results: ResultCache,
+
+    /// Set once the service starts shutting down
+    pub shutdown: Shutdown,
+}
+
+impl FromRef<AppState> for MetricsState {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for ActiveChecks {
+    fn from_ref(state: &AppState) -> Self {
+        state.checks.clone()
+    }
+}
+
+impl FromRef<AppState> for ResultCache {
+    fn from_ref(state: &AppState) -> Self {
+        state.results.clone()
+    }
+}
+
+impl FromRef<AppState> for Shutdown {
+    fn from_ref(state: &AppState) -> Self {
+        state.shutdown.clone()
+    }
+}
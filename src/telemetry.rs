@@ -1,13 +1,29 @@
+use axum::http::{HeaderMap, Request};
 use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{
+    Resource,
     logs::LoggerProvider,
     metrics::SdkMeterProvider,
-    trace::TracerProvider,
-    Resource,
+    propagation::TraceContextPropagator,
+    trace::{Sampler, TracerProvider},
 };
+use percent_encoding::percent_decode_str;
 use std::env;
-use tracing::info;
+use std::time::Duration;
+use tracing::{Span, info, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::env_parser::parse_duration;
+
+/// Downward API variables mapped to Kubernetes resource attributes
+const KUBERNETES_RESOURCE_ENVS: &[(&str, &str)] = &[
+    ("POD_NAME", "k8s.pod.name"),
+    ("POD_NAMESPACE", "k8s.namespace.name"),
+    ("NODE_NAME", "k8s.node.name"),
+];
 
 /// OpenTelemetry protocol type
 #[derive(Debug, Clone, PartialEq)]
@@ -37,8 +53,20 @@ pub struct OtelConfig {
     pub logs_exporter: ExporterType,
     /// Collector endpoint URL (OTEL_EXPORTER_OTLP_ENDPOINT)
     pub endpoint: String,
+    /// Traces endpoint URL (OTEL_EXPORTER_OTLP_TRACES_ENDPOINT)
+    pub traces_endpoint: String,
+    /// Metrics endpoint URL (OTEL_EXPORTER_OTLP_METRICS_ENDPOINT)
+    pub metrics_endpoint: String,
+    /// Logs endpoint URL (OTEL_EXPORTER_OTLP_LOGS_ENDPOINT)
+    pub logs_endpoint: String,
     /// Protocol type (OTEL_EXPORTER_OTLP_PROTOCOL)
     pub protocol: OtelProtocol,
+    /// Export timeout (OTEL_EXPORTER_OTLP_TIMEOUT)
+    pub timeout: Duration,
+    /// Traces sampler (OTEL_TRACES_SAMPLER and OTEL_TRACES_SAMPLER_ARG)
+    pub sampler: Sampler,
+    /// Additional resource attributes (OTEL_RESOURCE_ATTRIBUTES)
+    pub resource_attributes: Vec<(String, String)>,
     /// Service name (OTEL_SERVICE_NAME)
     pub service_name: String,
 }
@@ -46,7 +74,6 @@ pub struct OtelConfig {
 /// Container for all OpenTelemetry providers
 pub struct OtelProviders {
     pub tracer_provider: Option<TracerProvider>,
-    #[allow(dead_code)]
     pub meter_provider: Option<SdkMeterProvider>,
     pub logger_provider: Option<LoggerProvider>,
 }
@@ -71,6 +98,69 @@ fn parse_protocol() -> OtelProtocol {
         .unwrap_or(OtelProtocol::Grpc)
 }
 
+/// Parse a comma-separated list of key=value pairs with percent-encoded values
+/// Used by OTEL_RESOURCE_ATTRIBUTES
+fn parse_key_value_list(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = key.trim();
+
+            if key.is_empty() {
+                return None;
+            }
+
+            let value = percent_decode_str(value.trim()).decode_utf8().ok()?;
+
+            Some((key.to_string(), value.into_owned()))
+        })
+        .collect()
+}
+
+/// Parse the traces sampler, defaults to parentbased_always_on
+fn parse_sampler(sampler: Option<&str>, arg: Option<&str>) -> Sampler {
+    let ratio = || {
+        arg.and_then(|arg| arg.trim().parse::<f64>().ok())
+            .filter(|ratio| (0.0..=1.0).contains(ratio))
+            .unwrap_or(1.0)
+    };
+
+    match sampler.map(|s| s.trim().to_lowercase()).as_deref() {
+        Some("always_on") => Sampler::AlwaysOn,
+        Some("always_off") => Sampler::AlwaysOff,
+        Some("traceidratio") => Sampler::TraceIdRatioBased(ratio()),
+        Some("parentbased_always_off") => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        Some("parentbased_traceidratio") => {
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio())))
+        }
+        _ => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+    }
+}
+
+/// Default collector endpoint of a protocol
+fn default_endpoint(protocol: &OtelProtocol) -> &'static str {
+    match protocol {
+        OtelProtocol::Grpc => "http://localhost:4317",
+        OtelProtocol::Http => "http://localhost:4318",
+    }
+}
+
+/// Resolve the endpoint of a signal
+/// A per-signal endpoint is used as is, otherwise HTTP appends the signal path to the collector endpoint
+fn signal_endpoint(
+    signal_endpoint: Option<String>,
+    endpoint: &str,
+    protocol: &OtelProtocol,
+    path: &str,
+) -> String {
+    match (signal_endpoint, protocol) {
+        (Some(signal_endpoint), _) => signal_endpoint,
+        (None, OtelProtocol::Grpc) => endpoint.to_string(),
+        (None, OtelProtocol::Http) => format!("{}{}", endpoint.trim_end_matches('/'), path),
+    }
+}
+
 fn parse_bool_env(env_var: &str, default: bool) -> bool {
     env::var(env_var)
         .map(|v| v.to_lowercase() == "true")
@@ -80,16 +170,59 @@ fn parse_bool_env(env_var: &str, default: bool) -> bool {
 impl OtelConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Self {
+        let protocol = parse_protocol();
+        let endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .unwrap_or_else(|_| default_endpoint(&protocol).to_string());
+        let resource_attributes = env::var("OTEL_RESOURCE_ATTRIBUTES")
+            .map(|value| parse_key_value_list(&value))
+            .unwrap_or_default();
+
+        // OTEL_SERVICE_NAME takes precedence over service.name in OTEL_RESOURCE_ATTRIBUTES
+        let service_name = env::var("OTEL_SERVICE_NAME")
+            .ok()
+            .or_else(|| {
+                resource_attributes
+                    .iter()
+                    .find(|(key, _)| key == "service.name")
+                    .map(|(_, value)| value.clone())
+            })
+            .unwrap_or_else(|| "pmp-test-api".to_string());
+
         Self {
             disabled: parse_bool_env("OTEL_SDK_DISABLED", false),
             traces_exporter: parse_exporter_type("OTEL_TRACES_EXPORTER", ExporterType::Otlp),
             metrics_exporter: parse_exporter_type("OTEL_METRICS_EXPORTER", ExporterType::None),
             logs_exporter: parse_exporter_type("OTEL_LOGS_EXPORTER", ExporterType::None),
-            endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
-                .unwrap_or_else(|_| "http://localhost:4317".to_string()),
-            protocol: parse_protocol(),
-            service_name: env::var("OTEL_SERVICE_NAME")
-                .unwrap_or_else(|_| "pmp-test-api".to_string()),
+            traces_endpoint: signal_endpoint(
+                env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").ok(),
+                &endpoint,
+                &protocol,
+                "/v1/traces",
+            ),
+            metrics_endpoint: signal_endpoint(
+                env::var("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT").ok(),
+                &endpoint,
+                &protocol,
+                "/v1/metrics",
+            ),
+            logs_endpoint: signal_endpoint(
+                env::var("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT").ok(),
+                &endpoint,
+                &protocol,
+                "/v1/logs",
+            ),
+            endpoint,
+            protocol,
+            timeout: env::var("OTEL_EXPORTER_OTLP_TIMEOUT")
+                .ok()
+                .and_then(|value| parse_export_timeout(&value))
+                .unwrap_or(Duration::from_secs(10)),
+            sampler: parse_sampler(
+                env::var("OTEL_TRACES_SAMPLER").ok().as_deref(),
+                env::var("OTEL_TRACES_SAMPLER_ARG").ok().as_deref(),
+            ),
+            resource_attributes,
+            service_name,
         }
     }
 
@@ -101,39 +234,72 @@ impl OtelConfig {
     }
 }
 
-fn create_resource(service_name: &str) -> Resource {
-    Resource::new(vec![KeyValue::new("service.name", service_name.to_string())])
+/// Parse OTEL_EXPORTER_OTLP_TIMEOUT, an integer number of milliseconds as in the specification
+/// Durations with a unit (e.g. "10s") are also accepted
+fn parse_export_timeout(value: &str) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(millis) => Some(Duration::from_millis(millis)),
+        Err(_) => parse_duration(value),
+    }
+}
+
+/// Build the resource shared by all signals
+/// OTEL_RESOURCE_ATTRIBUTES override the detected attributes, the service name overrides both
+fn create_resource(config: &OtelConfig) -> Resource {
+    let mut attributes = vec![KeyValue::new("service.version", env!("CARGO_PKG_VERSION"))];
+
+    for (env_var, key) in KUBERNETES_RESOURCE_ENVS {
+        if let Ok(value) = env::var(env_var)
+            && !value.is_empty()
+        {
+            attributes.push(KeyValue::new(*key, value));
+        }
+    }
+
+    attributes.extend(
+        config
+            .resource_attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+    );
+    attributes.push(KeyValue::new("service.name", config.service_name.clone()));
+
+    Resource::new(attributes)
 }
 
 fn init_tracer_provider(config: &OtelConfig) -> Result<TracerProvider, Box<dyn std::error::Error>> {
     info!(
-        endpoint = %config.endpoint,
+        endpoint = %config.traces_endpoint,
         protocol = ?config.protocol,
         "Initializing OpenTelemetry tracer"
     );
 
-    let resource = create_resource(&config.service_name);
+    let resource = create_resource(config);
 
     let provider = match config.protocol {
         OtelProtocol::Grpc => {
             let exporter = opentelemetry_otlp::SpanExporter::builder()
                 .with_tonic()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.traces_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             TracerProvider::builder()
                 .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+                .with_sampler(config.sampler.clone())
                 .with_resource(resource)
                 .build()
         }
         OtelProtocol::Http => {
             let exporter = opentelemetry_otlp::SpanExporter::builder()
                 .with_http()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.traces_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             TracerProvider::builder()
                 .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+                .with_sampler(config.sampler.clone())
                 .with_resource(resource)
                 .build()
         }
@@ -144,20 +310,23 @@ fn init_tracer_provider(config: &OtelConfig) -> Result<TracerProvider, Box<dyn s
     Ok(provider)
 }
 
-fn init_meter_provider(config: &OtelConfig) -> Result<SdkMeterProvider, Box<dyn std::error::Error>> {
+fn init_meter_provider(
+    config: &OtelConfig,
+) -> Result<SdkMeterProvider, Box<dyn std::error::Error>> {
     info!(
-        endpoint = %config.endpoint,
+        endpoint = %config.metrics_endpoint,
         protocol = ?config.protocol,
         "Initializing OpenTelemetry meter"
     );
 
-    let resource = create_resource(&config.service_name);
+    let resource = create_resource(config);
 
     let provider = match config.protocol {
         OtelProtocol::Grpc => {
             let exporter = opentelemetry_otlp::MetricExporter::builder()
                 .with_tonic()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.metrics_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             let reader = opentelemetry_sdk::metrics::PeriodicReader::builder(
@@ -174,7 +343,8 @@ fn init_meter_provider(config: &OtelConfig) -> Result<SdkMeterProvider, Box<dyn
         OtelProtocol::Http => {
             let exporter = opentelemetry_otlp::MetricExporter::builder()
                 .with_http()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.metrics_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             let reader = opentelemetry_sdk::metrics::PeriodicReader::builder(
@@ -195,22 +365,66 @@ fn init_meter_provider(config: &OtelConfig) -> Result<SdkMeterProvider, Box<dyn
     Ok(provider)
 }
 
-fn init_logger_provider(
-    config: &OtelConfig,
-) -> Result<LoggerProvider, Box<dyn std::error::Error>> {
+/// Tracer provider writing spans to stdout as soon as they end
+fn init_console_tracer_provider(config: &OtelConfig) -> TracerProvider {
+    info!("Initializing OpenTelemetry console tracer");
+
+    TracerProvider::builder()
+        .with_simple_exporter(opentelemetry_stdout::SpanExporter::default())
+        .with_sampler(config.sampler.clone())
+        .with_resource(create_resource(config))
+        .build()
+}
+
+/// Tracer provider without exporter, its spans only carry the trace context from incoming
+/// requests to the services called by the checks
+fn init_propagation_tracer_provider(config: &OtelConfig) -> TracerProvider {
+    TracerProvider::builder()
+        .with_sampler(config.sampler.clone())
+        .build()
+}
+
+/// Meter provider writing metrics to stdout on every export interval
+fn init_console_meter_provider(config: &OtelConfig) -> SdkMeterProvider {
+    info!("Initializing OpenTelemetry console meter");
+
+    let reader = opentelemetry_sdk::metrics::PeriodicReader::builder(
+        opentelemetry_stdout::MetricExporter::default(),
+        opentelemetry_sdk::runtime::Tokio,
+    )
+    .build();
+
+    SdkMeterProvider::builder()
+        .with_reader(reader)
+        .with_resource(create_resource(config))
+        .build()
+}
+
+/// Logger provider writing log records to stdout as soon as they are emitted
+fn init_console_logger_provider(config: &OtelConfig) -> LoggerProvider {
+    info!("Initializing OpenTelemetry console logger");
+
+    LoggerProvider::builder()
+        .with_simple_exporter(opentelemetry_stdout::LogExporter::default())
+        .with_resource(create_resource(config))
+        .build()
+}
+
+fn init_logger_provider(config: &OtelConfig) -> Result<LoggerProvider, Box<dyn std::error::Error>> {
     info!(
-        endpoint = %config.endpoint,
+        endpoint = %config.logs_endpoint,
         protocol = ?config.protocol,
         "Initializing OpenTelemetry logger"
     );
 
-    let resource = create_resource(&config.service_name);
+    let resource = create_resource(config);
 
     let provider = match config.protocol {
         OtelProtocol::Grpc => {
             let exporter = opentelemetry_otlp::LogExporter::builder()
                 .with_tonic()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.logs_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             LoggerProvider::builder()
@@ -221,7 +435,8 @@ fn init_logger_provider(
         OtelProtocol::Http => {
             let exporter = opentelemetry_otlp::LogExporter::builder()
                 .with_http()
-                .with_endpoint(&config.endpoint)
+                .with_endpoint(&config.logs_endpoint)
+                .with_timeout(config.timeout)
                 .build()?;
 
             LoggerProvider::builder()
@@ -246,22 +461,26 @@ pub fn init_telemetry(config: &OtelConfig) -> Result<OtelProviders, Box<dyn std:
         "Initializing OpenTelemetry"
     );
 
-    let tracer_provider = if config.traces_exporter == ExporterType::Otlp {
-        Some(init_tracer_provider(config)?)
-    } else {
-        None
+    // Propagate W3C trace context (traceparent and tracestate) on incoming and outgoing requests
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let tracer_provider = match config.traces_exporter {
+        ExporterType::Otlp => Some(init_tracer_provider(config)?),
+        ExporterType::Console => Some(init_console_tracer_provider(config)),
+        // Traces are still propagated when they are not exported
+        ExporterType::None => Some(init_propagation_tracer_provider(config)),
     };
 
-    let meter_provider = if config.metrics_exporter == ExporterType::Otlp {
-        Some(init_meter_provider(config)?)
-    } else {
-        None
+    let meter_provider = match config.metrics_exporter {
+        ExporterType::Otlp => Some(init_meter_provider(config)?),
+        ExporterType::Console => Some(init_console_meter_provider(config)),
+        ExporterType::None => None,
     };
 
-    let logger_provider = if config.logs_exporter == ExporterType::Otlp {
-        Some(init_logger_provider(config)?)
-    } else {
-        None
+    let logger_provider = match config.logs_exporter {
+        ExporterType::Otlp => Some(init_logger_provider(config)?),
+        ExporterType::Console => Some(init_console_logger_provider(config)),
+        ExporterType::None => None,
     };
 
     Ok(OtelProviders {
@@ -271,8 +490,34 @@ pub fn init_telemetry(config: &OtelConfig) -> Result<OtelProviders, Box<dyn std:
     })
 }
 
+/// Create the span of an incoming request
+/// The span continues the trace of the caller when the request carries a W3C trace context
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let span = info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+
+    span
+}
+
+/// Inject the trace context of the current span into the headers of an outgoing request
+pub fn inject_trace_context(headers: &mut HeaderMap) {
+    let context = Span::current().context();
+
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
 /// Shutdown all OpenTelemetry providers gracefully
-#[allow(dead_code)]
 pub fn shutdown_telemetry(providers: OtelProviders) {
     if let Some(tracer_provider) = providers.tracer_provider
         && let Err(err) = tracer_provider.shutdown()
@@ -292,3 +537,83 @@ pub fn shutdown_telemetry(providers: OtelProviders) {
         eprintln!("Error shutting down logger provider: {:?}", err);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_value_list() {
+        let pairs = parse_key_value_list(
+            "deployment.environment=prod, team=core%20platform,invalid,=empty",
+        );
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("deployment.environment".to_string(), "prod".to_string()),
+                ("team".to_string(), "core platform".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_export_timeout() {
+        assert_eq!(parse_export_timeout("10000"), Some(Duration::from_secs(10)));
+        assert_eq!(
+            parse_export_timeout("500ms"),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(parse_export_timeout("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_export_timeout("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_sampler() {
+        assert!(matches!(parse_sampler(None, None), Sampler::ParentBased(_)));
+        assert!(matches!(
+            parse_sampler(Some("ALWAYS_OFF"), None),
+            Sampler::AlwaysOff
+        ));
+        assert!(matches!(
+            parse_sampler(Some("traceidratio"), Some("0.25")),
+            Sampler::TraceIdRatioBased(ratio) if ratio == 0.25
+        ));
+        // Invalid ratios fall back to sampling everything
+        assert!(matches!(
+            parse_sampler(Some("traceidratio"), Some("2")),
+            Sampler::TraceIdRatioBased(ratio) if ratio == 1.0
+        ));
+    }
+
+    #[test]
+    fn test_signal_endpoint() {
+        assert_eq!(
+            signal_endpoint(
+                None,
+                "http://collector:4318/",
+                &OtelProtocol::Http,
+                "/v1/traces"
+            ),
+            "http://collector:4318/v1/traces"
+        );
+        assert_eq!(
+            signal_endpoint(
+                None,
+                "http://collector:4317",
+                &OtelProtocol::Grpc,
+                "/v1/traces"
+            ),
+            "http://collector:4317"
+        );
+        assert_eq!(
+            signal_endpoint(
+                Some("http://traces:4318/custom".to_string()),
+                "http://collector:4318",
+                &OtelProtocol::Http,
+                "/v1/traces"
+            ),
+            "http://traces:4318/custom"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::check::load_checks;
+use crate::env_parser::{parse_bool, validate_env};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+/// How serious a configuration issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Suspicious but harmless, e.g. an unknown parameter
+    Warning,
+    /// A value is ignored or replaced with a default, or a check is dropped
+    Error,
+}
+
+/// Problem found while validating the configuration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+
+    /// Check kind, absent for global settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+
+    /// Check identifier, absent for global settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+
+    /// Check parameter (lowercase) or global variable the issue is about
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param: Option<String>,
+
+    /// What is wrong and what happens instead
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn warning(param: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, param, message)
+    }
+
+    pub fn error(param: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, param, message)
+    }
+
+    fn new(severity: Severity, param: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind: None,
+            identifier: None,
+            param: Some(param.into()),
+            message: message.into(),
+        }
+    }
+
+    /// Attach the issue to a check
+    pub fn for_check(mut self, kind: &str, identifier: &str) -> Self {
+        self.kind = Some(kind.to_string());
+        self.identifier = Some(identifier.to_string());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.kind, &self.identifier) {
+            (Some(kind), Some(identifier)) => write!(f, "{}/{}", kind, identifier)?,
+            (Some(kind), None) => write!(f, "{}", kind)?,
+            _ => write!(f, "config")?,
+        }
+
+        if let Some(param) = &self.param {
+            write!(f, " {}", param)?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Validate the whole configuration: check definitions from every source and server settings
+pub fn validate_config() -> Vec<ConfigIssue> {
+    with_env_issues(load_checks().issues)
+}
+
+/// Add the issues of the server settings to the issues of the checks
+/// Issues are sorted by severity (errors first), then by check
+pub fn with_env_issues(mut issues: Vec<ConfigIssue>) -> Vec<ConfigIssue> {
+    issues.extend(validate_env());
+
+    issues.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.identifier.cmp(&b.identifier))
+            .then_with(|| a.param.cmp(&b.param))
+    });
+
+    issues
+}
+
+/// Log every issue, errors at error level and warnings at warn level
+pub fn log_issues(issues: &[ConfigIssue]) {
+    for issue in issues {
+        let kind = issue.kind.as_deref().unwrap_or_default();
+        let identifier = issue.identifier.as_deref().unwrap_or_default();
+        let param = issue.param.as_deref().unwrap_or_default();
+
+        match issue.severity {
+            Severity::Error => error!(
+                event = "config_issue",
+                kind, identifier, param, "Invalid configuration: {}", issue
+            ),
+            Severity::Warning => warn!(
+                event = "config_issue",
+                kind, identifier, param, "Suspicious configuration: {}", issue
+            ),
+        }
+    }
+}
+
+/// Report a value that does not parse as `T`, e.g. a non-numeric port
+pub fn validate_parse<T: FromStr>(
+    params: &HashMap<String, String>,
+    param: &str,
+    expected: &str,
+    fallback: &str,
+) -> Option<ConfigIssue> {
+    let value = params.get(param)?;
+
+    value.trim().parse::<T>().is_err().then(|| {
+        ConfigIssue::error(
+            param,
+            format!(
+                "Invalid value '{}', expected {}, using {}",
+                value, expected, fallback
+            ),
+        )
+    })
+}
+
+/// Report a value that is not a boolean (e.g. `true`, `no` or `1`)
+pub fn validate_bool(
+    params: &HashMap<String, String>,
+    param: &str,
+    fallback: &str,
+) -> Option<ConfigIssue> {
+    let value = params.get(param)?;
+
+    parse_bool(value).is_none().then(|| {
+        ConfigIssue::error(
+            param,
+            format!("Invalid boolean '{}', using {}", value, fallback),
+        )
+    })
+}
+
+/// Report a value that is not one of the supported values
+pub fn validate_one_of(
+    params: &HashMap<String, String>,
+    param: &str,
+    supported: &[&str],
+) -> Option<ConfigIssue> {
+    let value = params.get(param)?;
+
+    (!supported.contains(&value.as_str())).then(|| {
+        ConfigIssue::error(
+            param,
+            format!(
+                "Unsupported value '{}', expected one of: {}",
+                value,
+                supported.join(", ")
+            ),
+        )
+    })
+}
+
+/// Report AWS credentials where only one of the key ID and the secret key is set,
+/// which makes the check silently fall back to the default credential chain
+pub fn validate_aws_credentials(params: &HashMap<String, String>) -> Option<ConfigIssue> {
+    match (
+        params.contains_key("access_key_id"),
+        params.contains_key("secret_access_key"),
+    ) {
+        (true, false) => Some(ConfigIssue::error(
+            "secret_access_key",
+            "ACCESS_KEY_ID is set without SECRET_ACCESS_KEY, using the default credential chain",
+        )),
+        (false, true) => Some(ConfigIssue::error(
+            "access_key_id",
+            "SECRET_ACCESS_KEY is set without ACCESS_KEY_ID, using the default credential chain",
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_helpers() {
+        let params: HashMap<String, String> = [
+            ("port", "54x2"),
+            ("driver", "oracle"),
+            ("access_key_id", "AKIA"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let port = validate_parse::<u16>(&params, "port", "a port number", "5432").unwrap();
+        assert!(port.is_error());
+        assert!(port.message.contains("'54x2'"));
+        assert!(validate_parse::<u16>(&params, "missing", "a number", "0").is_none());
+
+        let driver = validate_one_of(&params, "driver", &["postgres", "mysql"]).unwrap();
+        assert_eq!(
+            driver.to_string(),
+            "config driver: Unsupported value 'oracle', expected one of: postgres, mysql"
+        );
+
+        let credentials = validate_aws_credentials(&params)
+            .unwrap()
+            .for_check("s3", "ASSETS");
+        assert_eq!(credentials.param.as_deref(), Some("secret_access_key"));
+        assert!(credentials.to_string().starts_with("s3/ASSETS "));
+    }
+}